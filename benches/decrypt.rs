@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+use rsa_rust::RSA;
+
+const ITERATIONS: u32 = 50;
+
+fn time_decrypt(rsa: &RSA, ciphertext: &[u8]) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        rsa.decrypt_unblinded(ciphertext).expect("decryption failed");
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn time_decrypt_blinded(rsa: &RSA, ciphertext: &[u8]) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        rsa.decrypt(ciphertext).expect("decryption failed");
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let rsa = RSA::new(2048).expect("key generation failed");
+    let plain = RSA {
+        public_key: rsa.public_key.clone(),
+        private_key: rsa.private_key.clone(),
+        crt: None,
+    };
+    let ciphertext = rsa.encrypt(b"benchmark message").expect("message fits");
+
+    let with_crt = time_decrypt(&rsa, &ciphertext);
+    let without_crt = time_decrypt(&plain, &ciphertext);
+    let blinded = time_decrypt_blinded(&rsa, &ciphertext);
+
+    println!("decrypt 2048-bit (CRT):    {:?}", with_crt);
+    println!("decrypt 2048-bit (no CRT): {:?}", without_crt);
+    println!(
+        "speedup: {:.2}x",
+        without_crt.as_secs_f64() / with_crt.as_secs_f64()
+    );
+    println!("decrypt 2048-bit (CRT, blinded): {:?}", blinded);
+}
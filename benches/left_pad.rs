@@ -0,0 +1,52 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rsa_rust::rsa::BigUint;
+
+const ITERATIONS: u32 = 10_000;
+const MODULUS_BYTES: usize = 512; // 4096-bit key
+
+// What `encrypt` used to do: shift the whole vector once per missing byte
+fn insert_front(value: &BigUint) -> Vec<u8> {
+    let mut out = value.to_bytes_be();
+    while out.len() < MODULUS_BYTES {
+        out.insert(0, 0);
+    }
+    out
+}
+
+// Allocate the final size once and copy into the tail, as `encrypt` now does
+fn copy_into_tail(value: &BigUint) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let mut out = vec![0u8; MODULUS_BYTES];
+    out[MODULUS_BYTES - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+fn time(pad: fn(&BigUint) -> Vec<u8>, value: &BigUint) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(pad(black_box(value)));
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    // A one-byte value is the worst case: 511 leading zero bytes to fill
+    for (label, value) in [
+        ("1-byte value", BigUint::from(7u32)),
+        ("256-byte value", BigUint::from_bytes_be(&[0xab; 256])),
+        ("511-byte value", BigUint::from_bytes_be(&[0xab; 511])),
+    ] {
+        assert_eq!(insert_front(&value), copy_into_tail(&value));
+        let old = time(insert_front, &value);
+        let new = time(copy_into_tail, &value);
+        println!(
+            "{:>14}: insert loop {:?}, preallocated {:?} ({:.1}x)",
+            label,
+            old,
+            new,
+            old.as_secs_f64() / new.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+use rsa_rust::rsa::BigUint;
+use rsa_rust::{RSA, modpow_ct};
+
+const ITERATIONS: u32 = 20;
+
+fn time(f: impl Fn() -> BigUint) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+// Constant-time exponentiation against `BigUint::modpow` on the operations a
+// private key actually performs. `cargo bench --bench decrypt --features
+// fast-insecure` shows the same difference end to end.
+fn main() {
+    let rsa = RSA::new(2048).expect("key generation failed");
+    let crt = rsa
+        .crt_params()
+        .expect("generated keys have CRT parameters");
+    let (d, n) = &rsa.private_key;
+    let x = n - 12345u32;
+
+    for (label, exponent, modulus) in [
+        ("2048-bit d mod n", d, n),
+        ("1024-bit dp mod p", &crt.dp, &crt.p),
+    ] {
+        let fast = time(|| x.modpow(exponent, modulus));
+        let ct = time(|| modpow_ct(&x, exponent, modulus));
+        println!(
+            "{label}: modpow {fast:?}, modpow_ct {ct:?} ({:.2}x)",
+            ct.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,31 @@
+// Feeds arbitrary bytes to every decrypt entry point; each call must come back
+// as Ok or Err. Run with `cargo +nightly fuzz run decrypt` from the repo root.
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rsa_rust::{HashAlgorithm, RSA};
+
+// One fixed key for the whole run; generating one per input would dominate the time
+fn key() -> &'static RSA {
+    static KEY: OnceLock<RSA> = OnceLock::new();
+    KEY.get_or_init(|| {
+        RSA::new_with_rng(1024, &mut StdRng::seed_from_u64(0x5eed)).expect("key generation")
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let key = key();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    if let Ok(message) = key.decrypt_with_rng(data, &mut rng) {
+        assert!(message.len() <= key.max_message_len());
+    }
+    let _ = key.decrypt_unblinded(data);
+    let _ = key.decrypt_oaep(data, HashAlgorithm::Sha256, b"");
+    let _ = key.decrypt_chunked(data);
+    let _ = key.unseal(data);
+});
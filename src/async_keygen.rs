@@ -0,0 +1,62 @@
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::error::RsaError;
+use crate::rsa::RSA;
+
+type Outcome = thread::Result<Result<RSA, RsaError>>;
+
+#[derive(Default)]
+struct Shared {
+    outcome: Option<Outcome>,
+    waker: Option<Waker>,
+}
+
+// Resolves once the generation thread is done. Works under any executor: the
+// thread wakes whichever task polled last, so no runtime has to be running.
+pub struct KeyGenFuture {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Future for KeyGenFuture {
+    type Output = Result<RSA, RsaError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.outcome.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            // Re-raise a panic from the generation thread in the awaiting task
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl RSA {
+    // `RSA::new` on its own thread, so an async caller's executor is not blocked
+    // for the seconds a 4096-bit key can take. Dropping the future does not stop
+    // the search; the key is discarded when it finishes.
+    pub fn new_async(bit_len: usize) -> KeyGenFuture {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let worker = Arc::clone(&shared);
+        thread::spawn(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| RSA::new(bit_len)));
+            let waker = {
+                let mut shared = worker.lock().unwrap_or_else(|e| e.into_inner());
+                shared.outcome = Some(outcome);
+                shared.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+        KeyGenFuture { shared }
+    }
+}
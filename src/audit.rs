@@ -0,0 +1,325 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use num_bigint::BigUint;
+use num_traits::One;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng};
+
+use crate::error::RsaError;
+use crate::rsa::{RSA, check_key_size, gcd, primes_far_apart};
+
+// Below this the modulus is short of current recommendations (NIST SP 800-57)
+const RECOMMENDED_MODULUS_BITS: u64 = 2048;
+
+// Pollard rho steps spent looking for a small factor of n. The expected cost is near
+// the square root of the factor, so this reliably finds factors below about 2^20,
+// and takes a few tens of milliseconds on a 2048-bit modulus in a release build.
+const RHO_STEPS: usize = 1 << 11;
+
+// Fermat steps tried when the primes are unknown. The first one already factors n
+// when |p - q| is below about n^(1/4).
+const FERMAT_STEPS: usize = 256;
+
+// Weaknesses `RSA::audit` looks for. None of them makes a key unusable, which is
+// why they are warnings rather than `validate` errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyWarning {
+    // Pollard rho found a factor of n far smaller than an RSA prime
+    SmallFactor { bits: u64 },
+    // p and q too close together, so Fermat's method factors n (FIPS 186-4, B.3.1)
+    ClosePrimes,
+    // d < n^(1/4): within reach of Wiener's continued-fraction attack
+    SmallPrivateExponent { bits: u64, modulus_bits: u64 },
+    SmallModulus { bits: u64 },
+    // e outside 2^16 < e < 2^256 (NIST SP 800-56B)
+    PublicExponentOutOfRange,
+}
+
+impl fmt::Display for KeyWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyWarning::SmallFactor { bits } => {
+                write!(f, "modulus has a factor of only {} bits", bits)
+            }
+            KeyWarning::ClosePrimes => {
+                write!(f, "prime factors are close enough for Fermat factorization")
+            }
+            KeyWarning::SmallPrivateExponent { bits, modulus_bits } => write!(
+                f,
+                "private exponent of {} bits is below a quarter of the {}-bit modulus",
+                bits, modulus_bits
+            ),
+            KeyWarning::SmallModulus { bits } => write!(
+                f,
+                "modulus of {} bits is below the recommended {} bits",
+                bits, RECOMMENDED_MODULUS_BITS
+            ),
+            KeyWarning::PublicExponentOutOfRange => {
+                write!(f, "public exponent is outside the range 2^16 < e < 2^256")
+            }
+        }
+    }
+}
+
+impl RSA {
+    // Structured findings for key-management tooling, most severe first; empty
+    // for a key generated by `RSA::new`
+    pub fn audit(&self) -> Vec<KeyWarning> {
+        let (e, n) = &self.public_key;
+        let d = &*self.private_exponent();
+        let mut warnings = Vec::new();
+
+        if let Some(factor) = pollard_rho(n, RHO_STEPS) {
+            warnings.push(KeyWarning::SmallFactor {
+                bits: factor.bits(),
+            });
+        }
+        let close = match self.crt.as_ref() {
+            Some(crt) => {
+                let primes: Vec<&BigUint> = crt.primes().collect();
+                primes.iter().enumerate().any(|(i, p)| {
+                    primes[i + 1..].iter().any(|q| {
+                        let bits = p.bits().min(q.bits()) as usize;
+                        !primes_far_apart(p, q, bits)
+                    })
+                })
+            }
+            None => fermat_factors(n, FERMAT_STEPS),
+        };
+        if close {
+            warnings.push(KeyWarning::ClosePrimes);
+        }
+        if d.pow(4) < *n {
+            warnings.push(KeyWarning::SmallPrivateExponent {
+                bits: d.bits(),
+                modulus_bits: n.bits(),
+            });
+        }
+        if n.bits() < RECOMMENDED_MODULUS_BITS {
+            warnings.push(KeyWarning::SmallModulus { bits: n.bits() });
+        }
+        if e <= &BigUint::from(1u32 << 16) || e.bits() > 256 {
+            warnings.push(KeyWarning::PublicExponentOutOfRange);
+        }
+        warnings
+    }
+
+    // For long-lived services: re-runs `audit` and, if the key is weak or shorter
+    // than `bit_len`, replaces it in place with a fresh `RSA::new(bit_len)` key and
+    // returns true. `SmallModulus` on its own is judged against `bit_len` rather
+    // than 2048, so a deliberately short key is not replaced on every check.
+    #[cfg(feature = "std")]
+    pub fn regenerate_if_weak(&mut self, bit_len: usize) -> Result<bool, RsaError> {
+        self.regenerate_if_weak_with_rng(bit_len, &mut thread_rng())
+    }
+
+    pub fn regenerate_if_weak_with_rng<R: Rng + CryptoRng>(
+        &mut self,
+        bit_len: usize,
+        rng: &mut R,
+    ) -> Result<bool, RsaError> {
+        check_key_size(bit_len)?;
+        let weak = self.modulus_bits() < bit_len as u64
+            || self
+                .audit()
+                .iter()
+                .any(|w| !matches!(w, KeyWarning::SmallModulus { .. }));
+        if !weak {
+            return Ok(false);
+        }
+        // The old key's secrets are wiped as it is dropped
+        *self = RSA::new_with_rng(bit_len, rng)?;
+        Ok(true)
+    }
+}
+
+// Pollard's rho with f(x) = x^2 + 1 and Floyd cycle detection. The differences are
+// multiplied together so only every 64th step pays for a gcd.
+fn pollard_rho(n: &BigUint, steps: usize) -> Option<BigUint> {
+    if n.bits() < 3 {
+        return None;
+    }
+    let f = |x: &BigUint| (x * x + 1u32) % n;
+    let mut x = BigUint::from(2u32);
+    let mut y = x.clone();
+    let mut product = BigUint::one();
+    for step in 1..=steps {
+        x = f(&x);
+        y = f(&f(&y));
+        let diff = if x > y { &x - &y } else { &y - &x };
+        product = product * diff % n;
+        if step.is_multiple_of(64) || step == steps {
+            let g = gcd(&product, n);
+            // g == n means the batch overshot or the walk cycled: give up
+            if g == *n {
+                return None;
+            }
+            if !g.is_one() {
+                return Some(g);
+            }
+        }
+    }
+    None
+}
+
+// Tries a = ceil(sqrt(n)), a + 1, ... until a^2 - n is a square b^2, i.e. n = (a - b)(a + b)
+fn fermat_factors(n: &BigUint, steps: usize) -> bool {
+    let mut a = n.sqrt();
+    if &a * &a < *n {
+        a += 1u32;
+    }
+    for _ in 0..steps {
+        let b_squared = &a * &a - n;
+        let b = b_squared.sqrt();
+        if &b * &b == b_squared {
+            return true;
+        }
+        a += 1u32;
+    }
+    false
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::prime::{generate_prime_with_rounds, is_prime};
+
+    fn prime(bits: usize, rng: &mut StdRng) -> BigUint {
+        generate_prime_with_rounds(bits, 40, rng)
+    }
+
+    // The next prime above `p`, close enough that Fermat's method factors p * q at once
+    fn next_prime(p: &BigUint, rng: &mut StdRng) -> BigUint {
+        let mut q = p + 2u32;
+        while !is_prime(&q, 40, rng) {
+            q += 2u32;
+        }
+        q
+    }
+
+    fn key_from_primes(p: BigUint, q: BigUint, e: u32) -> RSA {
+        RSA::from_primes(p, q, &BigUint::from(e)).unwrap()
+    }
+
+    #[test]
+    fn generated_key_only_has_a_small_modulus() {
+        let rsa = RSA::new_with_rng(1024, &mut StdRng::seed_from_u64(89)).unwrap();
+        assert_eq!(rsa.audit(), [KeyWarning::SmallModulus { bits: 1024 }]);
+    }
+
+    #[test]
+    fn close_primes() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let p = prime(256, &mut rng);
+        let q = next_prime(&p, &mut rng);
+        let expected = [
+            KeyWarning::ClosePrimes,
+            KeyWarning::SmallModulus { bits: 512 },
+        ];
+        let rsa = key_from_primes(p, q, 65537);
+        assert_eq!(rsa.audit(), expected);
+
+        // Without the primes, Fermat's method finds them
+        let (e, n) = rsa.public_key.clone();
+        let bare = RSA::from_components(e, rsa.private_exponent().into_owned(), n);
+        assert_eq!(bare.audit(), expected);
+    }
+
+    #[test]
+    fn small_factor() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let rsa = key_from_primes(BigUint::from(65521u32), prime(500, &mut rng), 65537);
+        assert_eq!(
+            rsa.audit(),
+            [
+                KeyWarning::SmallFactor { bits: 16 },
+                KeyWarning::SmallModulus {
+                    bits: rsa.modulus_bits()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn wiener_small_private_exponent() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let small_d = BigUint::from((1u128 << 100) + 277);
+        // A key whose public exponent is small_d has small_d as its d when swapped
+        let rsa = loop {
+            let (p, q) = (prime(256, &mut rng), prime(256, &mut rng));
+            if let Some(rsa) = RSA::from_primes(p, q, &small_d) {
+                break rsa;
+            }
+        };
+        let n = rsa.public_key.1.clone();
+        let e = rsa.private_exponent().into_owned();
+        let weak = RSA::from_components(e, small_d, n);
+        let warnings = weak.audit();
+        assert_eq!(
+            warnings[0],
+            KeyWarning::SmallPrivateExponent {
+                bits: 101,
+                modulus_bits: 512
+            }
+        );
+        assert!(!warnings.contains(&KeyWarning::ClosePrimes));
+        assert!(matches!(warnings[1], KeyWarning::SmallModulus { .. }));
+    }
+
+    #[test]
+    fn exponent_three() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let rsa = loop {
+            let (p, q) = (prime(256, &mut rng), prime(256, &mut rng));
+            if let Some(rsa) = RSA::from_primes(p, q, &BigUint::from(3u32)) {
+                break rsa;
+            }
+        };
+        assert_eq!(
+            rsa.audit(),
+            [
+                KeyWarning::SmallModulus {
+                    bits: rsa.modulus_bits()
+                },
+                KeyWarning::PublicExponentOutOfRange,
+            ]
+        );
+    }
+
+    #[test]
+    fn regenerate_keeps_strong_keys() {
+        let mut rng = StdRng::seed_from_u64(70);
+        let mut rsa = RSA::new_with_rng(1024, &mut rng).unwrap();
+        let before = rsa.public_key.clone();
+        // Only `SmallModulus`, and the key is as long as asked for
+        assert!(!rsa.regenerate_if_weak_with_rng(1024, &mut rng).unwrap());
+        assert_eq!(rsa.public_key, before);
+        // A longer requirement replaces it
+        assert!(rsa.regenerate_if_weak_with_rng(1536, &mut rng).unwrap());
+        assert_eq!(rsa.modulus_bits(), 1536);
+        assert!(matches!(
+            rsa.regenerate_if_weak_with_rng(256, &mut rng),
+            Err(RsaError::KeyTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn regenerate_replaces_weak_keys() {
+        let mut rng = StdRng::seed_from_u64(71);
+        let p = prime(256, &mut rng);
+        let q = next_prime(&p, &mut rng);
+        let mut rsa = key_from_primes(p, q, 65537);
+        let before = rsa.public_key.clone();
+
+        assert!(rsa.regenerate_if_weak_with_rng(512, &mut rng).unwrap());
+        assert_ne!(rsa.public_key, before);
+        assert_eq!(rsa.modulus_bits(), 512);
+        assert_eq!(rsa.audit(), [KeyWarning::SmallModulus { bits: 512 }]);
+        assert!(!rsa.regenerate_if_weak_with_rng(512, &mut rng).unwrap());
+    }
+}
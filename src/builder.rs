@@ -0,0 +1,155 @@
+use core::ops::ControlFlow;
+
+use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use rand::rngs::ThreadRng;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng};
+
+use crate::error::RsaError;
+use crate::prime::{
+    PrimeGenEvent, generate_prime_with_progress, generate_prime_with_rounds,
+    generate_safe_prime_with_rounds, recommended_rounds,
+};
+use crate::rsa::RSA;
+
+// Key generation with every knob in one place:
+//
+//     RsaKeyGenBuilder::new().bits(3072).exponent(3u32).miller_rabin_rounds(80).generate()
+//
+// Unset options keep the `RSA::new` defaults: 2048 bits, e = 65537,
+// `prime::recommended_rounds` for the prime size, ordinary (not safe) primes, no
+// entropy check, no progress callback and `thread_rng`.
+pub struct RsaKeyGenBuilder<R, P = fn(PrimeGenEvent) -> ControlFlow<()>> {
+    bits: usize,
+    exponent: BigUint,
+    miller_rabin_rounds: Option<usize>,
+    safe_primes: bool,
+    check_entropy: bool,
+    progress: Option<P>,
+    rng: R,
+}
+
+#[cfg(feature = "std")]
+impl RsaKeyGenBuilder<ThreadRng> {
+    pub fn new() -> Self {
+        Self::with_rng(thread_rng())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for RsaKeyGenBuilder<ThreadRng> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Rng + CryptoRng> RsaKeyGenBuilder<R> {
+    // Starting point without `std`, where there is no default RNG
+    pub fn with_rng(rng: R) -> Self {
+        RsaKeyGenBuilder {
+            bits: 2048,
+            exponent: BigUint::from(65537u32),
+            miller_rabin_rounds: None,
+            safe_primes: false,
+            check_entropy: false,
+            progress: None,
+            rng,
+        }
+    }
+}
+
+impl<R, P> RsaKeyGenBuilder<R, P>
+where
+    R: Rng + CryptoRng,
+    P: FnMut(PrimeGenEvent) -> ControlFlow<()>,
+{
+    pub fn bits(mut self, bits: usize) -> Self {
+        self.bits = bits;
+        self
+    }
+
+    pub fn exponent(mut self, e: impl Into<BigUint>) -> Self {
+        self.exponent = e.into();
+        self
+    }
+
+    // Can only raise the count: `generate` fails with `TooFewRounds` below
+    // `prime::recommended_rounds` for the prime size
+    pub fn miller_rabin_rounds(mut self, rounds: usize) -> Self {
+        self.miller_rabin_rounds = Some(rounds);
+        self
+    }
+
+    // See `RSA::new_safe_primes` for the cost
+    pub fn safe_primes(mut self, safe_primes: bool) -> Self {
+        self.safe_primes = safe_primes;
+        self
+    }
+
+    // Fails with `RsaError::WeakRandomness` if p and q agree in their top bits or
+    // p - 1 and q - 1 share an implausibly large factor, the signature of an RNG
+    // seeded from too little entropy (e.g. the same seed on every boot). Off by default.
+    pub fn check_entropy(mut self, check: bool) -> Self {
+        self.check_entropy = check;
+        self
+    }
+
+    // Called as in `RSA::new_with_progress`; returning `ControlFlow::Break` stops
+    // generation with `RsaError::Cancelled`. Safe-prime searches only report
+    // `PrimeFound`, once per prime.
+    pub fn progress<P2>(self, callback: P2) -> RsaKeyGenBuilder<R, P2>
+    where
+        P2: FnMut(PrimeGenEvent) -> ControlFlow<()>,
+    {
+        RsaKeyGenBuilder {
+            bits: self.bits,
+            exponent: self.exponent,
+            miller_rabin_rounds: self.miller_rabin_rounds,
+            safe_primes: self.safe_primes,
+            check_entropy: self.check_entropy,
+            progress: Some(callback),
+            rng: self.rng,
+        }
+    }
+
+    // Replaces the RNG, e.g. with a seeded one for reproducible keys in tests
+    pub fn rng<R2: Rng + CryptoRng>(self, rng: R2) -> RsaKeyGenBuilder<R2, P> {
+        RsaKeyGenBuilder {
+            bits: self.bits,
+            exponent: self.exponent,
+            miller_rabin_rounds: self.miller_rabin_rounds,
+            safe_primes: self.safe_primes,
+            check_entropy: self.check_entropy,
+            progress: self.progress,
+            rng,
+        }
+    }
+
+    pub fn generate(mut self) -> Result<RSA, RsaError> {
+        let min = recommended_rounds(self.bits / 2);
+        let rounds = self.miller_rabin_rounds.unwrap_or(min);
+        if rounds < min {
+            return Err(RsaError::TooFewRounds { got: rounds, min });
+        }
+        let safe_primes = self.safe_primes;
+        let mut progress = self.progress;
+        let next_prime = |bits, rng: &mut R| match (safe_primes, progress.as_mut()) {
+            (false, None) => Ok(generate_prime_with_rounds(bits, rounds, rng)),
+            (false, Some(callback)) => {
+                generate_prime_with_progress(bits, rounds, rng, callback).ok_or(RsaError::Cancelled)
+            }
+            (true, None) => Ok(generate_safe_prime_with_rounds(bits, rounds, rng)),
+            (true, Some(callback)) => {
+                let prime = generate_safe_prime_with_rounds(bits, rounds, rng);
+                match callback(PrimeGenEvent::PrimeFound) {
+                    ControlFlow::Continue(()) => Ok(prime),
+                    ControlFlow::Break(()) => Err(RsaError::Cancelled),
+                }
+            }
+        };
+        let check = self.check_entropy;
+        RSA::generate_with(self.bits, self.exponent, &mut self.rng, check, next_prime)
+    }
+}
@@ -0,0 +1,94 @@
+// Constant-time helpers. Masks are all-ones (true) or all-zeros (false), and no
+// function branches on its inputs.
+
+use alloc::vec::Vec;
+
+use crate::wipe::wipe_bytes;
+
+pub(crate) fn ct_is_zero(x: u32) -> u32 {
+    (((x | x.wrapping_neg()) >> 31) ^ 1).wrapping_neg()
+}
+
+pub(crate) fn ct_eq_u32(a: u32, b: u32) -> u32 {
+    ct_is_zero(a ^ b)
+}
+
+pub(crate) fn ct_lt(a: u32, b: u32) -> u32 {
+    ((a ^ ((a ^ b) | (a.wrapping_sub(b) ^ b))) >> 31).wrapping_neg()
+}
+
+pub(crate) fn ct_select(mask: u32, a: u32, b: u32) -> u32 {
+    (mask & a) | (!mask & b)
+}
+
+// Byte-slice equality that always scans every byte; the length itself is treated
+// as public. Verification paths compare attacker-chosen input against a secret or
+// expected value, and a `==` that stops at the first mismatch reports how many
+// leading bytes were right. That lets an attacker build a forged signature, tag or
+// padding one byte at a time, so every such comparison goes through here.
+pub(crate) fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u32, |acc, (x, y)| acc | u32::from(x ^ y));
+    ct_is_zero(diff) != 0
+}
+
+// a <= x <= b
+fn ct_in_range(x: u32, a: u8, b: u8) -> u32 {
+    !ct_lt(x, u32::from(a)) & !ct_lt(u32::from(b), x)
+}
+
+// Standard base64 (with padding) for secret material. Every character is looked up
+// without branching on it and every one is read, so timing does not depend on the
+// content or on where an invalid character sits. Only the length and the number of
+// '=' are treated as public, as the decoded length reveals them anyway. Rejects
+// what `BASE64_STANDARD` rejects, including non-zero bits left over before the
+// padding, but does not report where the input went wrong.
+pub(crate) fn ct_base64_decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+    let pad = encoded.iter().rev().take(2).take_while(|&&c| c == b'=').count();
+    let mut out = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut invalid = 0u32;
+    let mut acc = 0u32;
+
+    let value_len = encoded.len() - pad;
+    for (i, &c) in encoded.iter().enumerate() {
+        let c = u32::from(c);
+        let upper = ct_in_range(c, b'A', b'Z');
+        let lower = ct_in_range(c, b'a', b'z');
+        let digit = ct_in_range(c, b'0', b'9');
+        let plus = ct_eq_u32(c, u32::from(b'+'));
+        let slash = ct_eq_u32(c, u32::from(b'/'));
+        let value = (upper & c.wrapping_sub(65))
+            | (lower & c.wrapping_sub(71))
+            | (digit & c.wrapping_add(4))
+            | (plus & 62)
+            | (slash & 63);
+        // Padding positions contribute zero bits and are not alphabet checked
+        if i < value_len {
+            invalid |= !(upper | lower | digit | plus | slash);
+            acc = (acc << 6) | value;
+        } else {
+            acc <<= 6;
+        }
+        if i % 4 == 3 {
+            out.extend_from_slice(&acc.to_be_bytes()[1..]);
+            acc = 0;
+        }
+    }
+
+    // The bytes covered by padding must have decoded to zero
+    let produced = out.len() - pad;
+    for &b in &out[produced..] {
+        invalid |= !ct_is_zero(u32::from(b));
+    }
+    if invalid != 0 {
+        wipe_bytes(&mut out);
+        return None;
+    }
+    out.truncate(produced);
+    Some(out)
+}
@@ -0,0 +1,124 @@
+// Minimal DER encoding for the ASN.1 structures used by the key formats
+
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+
+pub(crate) fn encode_length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+        return;
+    }
+    let bytes = len.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    out.push(0x80 | (bytes.len() - skip) as u8);
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+pub(crate) fn encode_tlv(tag: u8, content: &[u8], out: &mut Vec<u8>) {
+    out.push(tag);
+    encode_length(content.len(), out);
+    out.extend_from_slice(content);
+}
+
+pub(crate) fn encode_integer(value: &BigUint, out: &mut Vec<u8>) {
+    let mut bytes = value.to_bytes_be();
+    // INTEGER is signed, so a set high bit needs a leading zero
+    if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0x00);
+    }
+    encode_tlv(TAG_INTEGER, &bytes, out);
+}
+
+pub(crate) fn encode_sequence(items: &[&BigUint]) -> Vec<u8> {
+    let mut content = Vec::new();
+    for item in items {
+        encode_integer(item, &mut content);
+    }
+    let mut out = Vec::new();
+    encode_tlv(TAG_SEQUENCE, &content, &mut out);
+    out
+}
+
+pub(crate) const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+
+// rsaEncryption, 1.2.840.113549.1.1.1
+pub(crate) const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+// PBES2, 1.2.840.113549.1.5.13
+#[cfg(feature = "std")]
+pub(crate) const OID_PBES2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+// PBKDF2, 1.2.840.113549.1.5.12
+#[cfg(feature = "std")]
+pub(crate) const OID_PBKDF2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0c];
+// hmacWithSHA256, 1.2.840.113549.2.9
+#[cfg(feature = "std")]
+pub(crate) const OID_HMAC_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x09];
+// aes256-CBC, 2.16.840.1.101.3.4.1.42
+#[cfg(feature = "std")]
+pub(crate) const OID_AES256_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+
+// Sequential reader over DER-encoded bytes; every read fails with None on malformed input
+pub(crate) struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        DerReader { data }
+    }
+
+    pub(crate) fn read_tlv(&mut self, tag: u8) -> Option<&'a [u8]> {
+        let (&actual, rest) = self.data.split_first()?;
+        if actual != tag {
+            return None;
+        }
+        let (&first, mut rest) = rest.split_first()?;
+
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > core::mem::size_of::<usize>() || rest.len() < count {
+                return None;
+            }
+            let (len_bytes, tail) = rest.split_at(count);
+            rest = tail;
+            len_bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize)
+        };
+
+        if rest.len() < len {
+            return None;
+        }
+        let (content, tail) = rest.split_at(len);
+        self.data = tail;
+        Some(content)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    pub(crate) fn read_sequence(&mut self) -> Option<DerReader<'a>> {
+        self.read_tlv(TAG_SEQUENCE).map(DerReader::new)
+    }
+
+    pub(crate) fn read_integer(&mut self) -> Option<BigUint> {
+        let content = self.read_tlv(TAG_INTEGER)?;
+        // Only non-negative integers appear in RSA key structures
+        if content.is_empty() || content[0] & 0x80 != 0 {
+            return None;
+        }
+        Some(BigUint::from_bytes_be(content))
+    }
+}
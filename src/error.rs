@@ -0,0 +1,142 @@
+use core::fmt;
+
+#[derive(Debug)]
+pub enum RsaError {
+    NonInvertibleExponent,
+    InvalidExponent,
+    InvalidPadding,
+    OaepLabelMismatch,
+    InvalidCiphertextLength,
+    InvalidFrame,
+    CiphertextOutOfRange,
+    DecryptionFailed,
+    KeyTooSmall {
+        bits: usize,
+        min: usize,
+    },
+    InvalidPrimeCount {
+        got: usize,
+        max: usize,
+    },
+    InvalidSaltLength {
+        got: usize,
+        max: usize,
+    },
+    TooFewRounds {
+        got: usize,
+        min: usize,
+    },
+    MessageTooLong {
+        got: usize,
+        max: usize,
+    },
+    MissingPrimes,
+    InvalidPem,
+    InvalidDer,
+    InvalidKeyBytes,
+    InvalidHex,
+    UnsupportedAlgorithm,
+    KeyValidationFailed(&'static str),
+    Cancelled,
+    WeakRandomness,
+    #[cfg(feature = "std")]
+    InvalidKeyJson(serde_json::Error),
+    #[cfg(feature = "std")]
+    InvalidValue(serde_json::Error),
+    InvalidKeyEncoding(base64::DecodeError),
+    InvalidSecretEncoding,
+}
+
+impl fmt::Display for RsaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsaError::NonInvertibleExponent => {
+                write!(f, "public exponent is not invertible modulo phi(n)")
+            }
+            RsaError::InvalidExponent => write!(f, "public exponent must be odd and at least 3"),
+            RsaError::InvalidPadding => write!(f, "invalid padding in decrypted message"),
+            RsaError::OaepLabelMismatch => write!(
+                f,
+                "OAEP label does not match (or the ciphertext is corrupt or for another key)"
+            ),
+            RsaError::InvalidCiphertextLength => {
+                write!(f, "ciphertext length does not match the key")
+            }
+            RsaError::InvalidFrame => write!(f, "missing or malformed ciphertext frame header"),
+            RsaError::CiphertextOutOfRange => {
+                write!(f, "ciphertext is not less than the modulus")
+            }
+            RsaError::DecryptionFailed => write!(f, "ciphertext failed authentication"),
+            RsaError::KeyTooSmall { bits, min } => write!(
+                f,
+                "key size of {} bits is below the minimum of {} bits",
+                bits, min
+            ),
+            RsaError::InvalidPrimeCount { got, max } => write!(
+                f,
+                "{} primes requested, but this key size supports 2 to {}",
+                got, max
+            ),
+            RsaError::InvalidSaltLength { got, max } => write!(
+                f,
+                "salt of {} bytes exceeds the {} byte limit for this key and hash",
+                got, max
+            ),
+            RsaError::TooFewRounds { got, min } => write!(
+                f,
+                "{} Miller-Rabin rounds requested, but this key size needs at least {}",
+                got, min
+            ),
+            RsaError::MessageTooLong { got, max } => write!(
+                f,
+                "message of {} bytes exceeds the {} byte limit for this key",
+                got, max
+            ),
+            RsaError::MissingPrimes => write!(f, "operation requires the prime factors of n"),
+            RsaError::InvalidPem => write!(f, "malformed PEM armor"),
+            RsaError::InvalidDer => write!(f, "malformed DER key structure"),
+            RsaError::InvalidKeyBytes => write!(f, "malformed binary key encoding"),
+            RsaError::InvalidHex => write!(f, "invalid hex string"),
+            RsaError::UnsupportedAlgorithm => write!(f, "unsupported key algorithm or version"),
+            RsaError::KeyValidationFailed(reason) => write!(f, "key validation failed: {}", reason),
+            RsaError::Cancelled => write!(f, "key generation was cancelled"),
+            RsaError::WeakRandomness => {
+                write!(f, "generated primes look degenerate; the RNG may be poorly seeded")
+            }
+            #[cfg(feature = "std")]
+            RsaError::InvalidKeyJson(err) => write!(f, "invalid key JSON: {}", err),
+            #[cfg(feature = "std")]
+            RsaError::InvalidValue(err) => write!(f, "value could not be (de)serialized: {}", err),
+            RsaError::InvalidKeyEncoding(err) => write!(f, "invalid base64 in key: {}", err),
+            RsaError::InvalidSecretEncoding => write!(f, "invalid base64 in private key"),
+        }
+    }
+}
+
+impl core::error::Error for RsaError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            RsaError::InvalidKeyJson(err) => Some(err),
+            #[cfg(feature = "std")]
+            RsaError::InvalidValue(err) => Some(err),
+            // base64 only implements Error for DecodeError with its `std` feature
+            #[cfg(feature = "std")]
+            RsaError::InvalidKeyEncoding(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<serde_json::Error> for RsaError {
+    fn from(err: serde_json::Error) -> Self {
+        RsaError::InvalidKeyJson(err)
+    }
+}
+
+impl From<base64::DecodeError> for RsaError {
+    fn from(err: base64::DecodeError) -> Self {
+        RsaError::InvalidKeyEncoding(err)
+    }
+}
@@ -0,0 +1,82 @@
+use crate::error::RsaError;
+use crate::hash::HashAlgorithm;
+use crate::rsa::{PaddingScheme, RSA, RSAPublicKey, modulus_byte_len};
+
+// Self-describing ciphertexts for storage, so a later reader knows how to decrypt.
+// Framed layout: ["RSAF"][version: 1][padding: 1][hash: 1][ciphertext]
+//   padding: 1 = PKCS#1 v1.5, 2 = OAEP (empty label)
+//   hash:    0 = none (PKCS#1 v1.5), 1 = SHA-256, 2 = SHA-384
+// The ciphertext is the full modulus length, so the key size follows from the frame.
+
+const MAGIC: &[u8; 4] = b"RSAF";
+const FRAME_VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 3;
+
+fn scheme_ids(scheme: PaddingScheme) -> [u8; 2] {
+    match scheme {
+        PaddingScheme::Pkcs1v15 => [1, 0],
+        PaddingScheme::Oaep(HashAlgorithm::Sha256) => [2, 1],
+        PaddingScheme::Oaep(HashAlgorithm::Sha384) => [2, 2],
+    }
+}
+
+// Unknown ids are assumed to come from a newer version of the format
+fn parse_scheme(padding: u8, hash: u8) -> Result<PaddingScheme, RsaError> {
+    match (padding, hash) {
+        (1, 0) => Ok(PaddingScheme::Pkcs1v15),
+        (1, _) => Err(RsaError::InvalidFrame),
+        (2, 1) => Ok(PaddingScheme::Oaep(HashAlgorithm::Sha256)),
+        (2, 2) => Ok(PaddingScheme::Oaep(HashAlgorithm::Sha384)),
+        _ => Err(RsaError::UnsupportedAlgorithm),
+    }
+}
+
+impl RSAPublicKey {
+    pub fn encrypt_framed(
+        &self,
+        message: &[u8],
+        scheme: PaddingScheme,
+    ) -> Result<Vec<u8>, RsaError> {
+        let ciphertext = match scheme {
+            PaddingScheme::Pkcs1v15 => self.encrypt(message)?,
+            PaddingScheme::Oaep(hash) => self.encrypt_oaep(message, hash, b"")?,
+        };
+        let mut framed = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        framed.extend_from_slice(MAGIC);
+        framed.push(FRAME_VERSION);
+        framed.extend_from_slice(&scheme_ids(scheme));
+        framed.extend_from_slice(&ciphertext);
+        Ok(framed)
+    }
+}
+
+impl RSA {
+    pub fn encrypt_framed(
+        &self,
+        message: &[u8],
+        scheme: PaddingScheme,
+    ) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_framed(message, scheme)
+    }
+
+    // Checks the header and decrypts with the padding it names. A missing or damaged
+    // header, or a ciphertext that is not the full modulus length, fails with
+    // `InvalidFrame`, a newer version or scheme with `UnsupportedAlgorithm`.
+    pub fn decrypt_framed(&self, framed: &[u8]) -> Result<Vec<u8>, RsaError> {
+        if framed.len() < HEADER_LEN || &framed[..MAGIC.len()] != MAGIC {
+            return Err(RsaError::InvalidFrame);
+        }
+        let (version, padding, hash) = (framed[4], framed[5], framed[6]);
+        if version != FRAME_VERSION {
+            return Err(RsaError::UnsupportedAlgorithm);
+        }
+        let ciphertext = &framed[HEADER_LEN..];
+        if ciphertext.len() != modulus_byte_len(&self.private_key.1) {
+            return Err(RsaError::InvalidFrame);
+        }
+        match parse_scheme(padding, hash)? {
+            PaddingScheme::Pkcs1v15 => self.decrypt(ciphertext),
+            PaddingScheme::Oaep(hash) => self.decrypt_oaep(ciphertext, hash, b""),
+        }
+    }
+}
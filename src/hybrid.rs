@@ -0,0 +1,159 @@
+use rand::{Rng, thread_rng};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::aes::{AesKey, KEY_LEN, NONCE_LEN, gcm_decrypt, gcm_encrypt};
+use crate::error::RsaError;
+use crate::hash::HashAlgorithm;
+use crate::rsa::{RSA, RSAPublicKey, modulus_byte_len};
+use crate::wipe::wipe_bytes;
+
+// Hybrid RSA-OAEP + AES-256-GCM encryption.
+// Sealed layout: [enc_key_len: u16 BE][enc_key][nonce: 12][ciphertext || tag: 16]
+
+const KEY_WRAP_HASH: HashAlgorithm = HashAlgorithm::Sha256;
+
+// One payload encrypted once for many recipients ("enveloped data"): the AES key is
+// wrapped under each recipient's public key, the body is shared
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    pub wrapped_keys: Vec<Vec<u8>>, // RSA-OAEP, in the order the recipients were given
+    pub ciphertext: Vec<u8>,        // [nonce: 12][ciphertext || tag: 16]
+}
+
+// Fails if any recipient's key is too small to wrap a 256-bit AES key under OAEP
+pub fn encrypt_to_recipients(
+    plaintext: &[u8],
+    keys: &[RSAPublicKey],
+) -> Result<Envelope, RsaError> {
+    let mut rng = thread_rng();
+    let key = AesKey::random(&mut rng);
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut nonce);
+
+    let wrapped_keys = keys
+        .iter()
+        .map(|recipient| recipient.wrap_key(&key.0))
+        .collect::<Result<_, _>>()?;
+
+    let mut ciphertext = nonce.to_vec();
+    ciphertext.extend(gcm_encrypt(&key.0, &nonce, plaintext, b""));
+    Ok(Envelope {
+        wrapped_keys,
+        ciphertext,
+    })
+}
+
+impl RSAPublicKey {
+    // Encrypts a raw symmetric key (not a message) under RSA-OAEP with SHA-256 and
+    // an empty label, the same wrapping `seal` uses. The key has to fit in one
+    // block: at most the modulus size minus 66 bytes, e.g. 190 bytes for 2048 bits.
+    pub fn wrap_key(&self, key_bytes: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.encrypt_oaep(key_bytes, KEY_WRAP_HASH, b"")
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let mut rng = thread_rng();
+        let key = AesKey::random(&mut rng);
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut nonce);
+
+        let enc_key = self.wrap_key(&key.0)?;
+        let body = gcm_encrypt(&key.0, &nonce, plaintext, b"");
+
+        let mut blob = Vec::with_capacity(2 + enc_key.len() + NONCE_LEN + body.len());
+        blob.extend_from_slice(&(enc_key.len() as u16).to_be_bytes());
+        blob.extend_from_slice(&enc_key);
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&body);
+        Ok(blob)
+    }
+
+    // Serializes `value` to JSON and seals it; the output is an ordinary `seal` blob
+    pub fn encrypt_value<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, RsaError> {
+        let mut json = serde_json::to_vec(value).map_err(RsaError::InvalidValue)?;
+        let sealed = self.seal(&json);
+        wipe_bytes(&mut json);
+        sealed
+    }
+}
+
+impl RSA {
+    pub fn wrap_key(&self, key_bytes: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.public().wrap_key(key_bytes)
+    }
+
+    // Unlike `decrypt_oaep`, a wrapped key shorter than the modulus is rejected, as
+    // `wrap_key` always produces exactly the modulus size
+    pub fn unwrap_key(&self, wrapped: &[u8]) -> Result<Vec<u8>, RsaError> {
+        if wrapped.len() != modulus_byte_len(&self.public_key.1) {
+            return Err(RsaError::InvalidCiphertextLength);
+        }
+        self.decrypt_oaep(wrapped, KEY_WRAP_HASH, b"")
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.public().seal(plaintext)
+    }
+
+    pub fn encrypt_value<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_value(value)
+    }
+
+    // Inverse of `encrypt_value`. Authentication happens before parsing, so a
+    // tampered blob fails with `DecryptionFailed`, never `InvalidValue`.
+    pub fn decrypt_value<T: DeserializeOwned>(&self, blob: &[u8]) -> Result<T, RsaError> {
+        let mut json = self.unseal(blob)?;
+        let value = serde_json::from_slice(&json).map_err(RsaError::InvalidValue);
+        wipe_bytes(&mut json);
+        value
+    }
+
+    pub fn unseal(&self, blob: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let (len_bytes, rest) = blob
+            .split_at_checked(2)
+            .ok_or(RsaError::InvalidCiphertextLength)?;
+        let enc_key_len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+        let (enc_key, rest) = rest
+            .split_at_checked(enc_key_len)
+            .ok_or(RsaError::InvalidCiphertextLength)?;
+        let (nonce, body) = rest
+            .split_at_checked(NONCE_LEN)
+            .ok_or(RsaError::InvalidCiphertextLength)?;
+
+        let key = self.unwrap_aes_key(enc_key)?;
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().unwrap();
+
+        gcm_decrypt(&key.0, &nonce, body, b"").ok_or(RsaError::DecryptionFailed)
+    }
+
+    // `unwrap_key` for an AES-256 key; any other length is `InvalidPadding`. The
+    // unwrapped bytes are wiped here, the returned key when it is dropped.
+    pub(crate) fn unwrap_aes_key(&self, wrapped: &[u8]) -> Result<AesKey, RsaError> {
+        let mut bytes = self.unwrap_key(wrapped)?;
+        let key = <[u8; KEY_LEN]>::try_from(bytes.as_slice()).map(AesKey);
+        wipe_bytes(&mut bytes);
+        key.map_err(|_| RsaError::InvalidPadding)
+    }
+
+    // Finds this key's entry by trying every wrapped key of the right size, so the
+    // recipient does not need to know its position; costs one private-key operation
+    // per candidate
+    pub fn open_envelope(&self, envelope: &Envelope) -> Result<Vec<u8>, RsaError> {
+        let (nonce, body) = envelope
+            .ciphertext
+            .split_at_checked(NONCE_LEN)
+            .ok_or(RsaError::InvalidCiphertextLength)?;
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().unwrap();
+
+        let modulus_bytes = modulus_byte_len(&self.public_key.1);
+        let key = envelope
+            .wrapped_keys
+            .iter()
+            .filter(|wrapped| wrapped.len() == modulus_bytes)
+            .find_map(|wrapped| self.unwrap_aes_key(wrapped).ok())
+            .ok_or(RsaError::DecryptionFailed)?;
+
+        gcm_decrypt(&key.0, &nonce, body, b"").ok_or(RsaError::DecryptionFailed)
+    }
+}
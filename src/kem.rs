@@ -0,0 +1,128 @@
+use alloc::vec::Vec;
+
+use num_bigint::{BigUint, RandBigInt};
+use rand::{CryptoRng, Rng};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use crate::error::RsaError;
+use crate::hash::HashAlgorithm;
+use crate::rsa::{RSA, RSAPublicKey, modulus_byte_len, to_padded_bytes};
+use crate::wipe::{wipe_biguint, wipe_bytes};
+
+// RSA-KEM (ISO/IEC 18033-2, section 11.5): the sender picks a random z in [0, n),
+// sends c = z^e mod n and both sides derive the shared key as KDF(z). There is no
+// padding to check, so decapsulation never fails on a well-formed ciphertext; a
+// tampered c just yields a different, useless key.
+// Ciphertext and KDF input are both the modulus size, big-endian.
+
+// Key derivation functions from ISO/IEC 18033-2, section 6.2.3. KDF1 is MGF1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    // Hash(secret || counter) for counter = 0, 1, ...
+    Kdf1(HashAlgorithm),
+    // Hash(secret || counter) for counter = 1, 2, ... (also ANSI X9.63)
+    Kdf2(HashAlgorithm),
+}
+
+impl Kdf {
+    pub fn derive(&self, secret: &[u8], length: usize) -> Vec<u8> {
+        let (hash, first) = match *self {
+            Kdf::Kdf1(hash) => (hash, 0u32),
+            Kdf::Kdf2(hash) => (hash, 1u32),
+        };
+        let mut key = Vec::with_capacity(length + hash.output_len());
+        let mut block = Vec::with_capacity(secret.len() + 4);
+        let mut counter = first;
+        while key.len() < length {
+            block.clear();
+            block.extend_from_slice(secret);
+            block.extend_from_slice(&counter.to_be_bytes());
+            key.extend_from_slice(&hash.digest(&block));
+            counter += 1;
+        }
+        wipe_bytes(&mut block);
+        wipe_bytes(&mut key[length..]);
+        key.truncate(length);
+        key
+    }
+}
+
+// KDF(I2OSP(z, modulus_bytes)), wiping z and its encoding
+fn derive_from(mut z: BigUint, modulus_bytes: usize, kdf: Kdf, key_len: usize) -> Vec<u8> {
+    let mut encoded = to_padded_bytes(&z, modulus_bytes);
+    let key = kdf.derive(&encoded, key_len);
+    wipe_bytes(&mut encoded);
+    wipe_biguint(&mut z);
+    key
+}
+
+impl RSAPublicKey {
+    // Returns (ciphertext, shared key); the ciphertext is the modulus size and the
+    // key is `key_len` bytes of `kdf` output
+    #[cfg(feature = "std")]
+    pub fn encapsulate(&self, kdf: Kdf, key_len: usize) -> (Vec<u8>, Vec<u8>) {
+        self.encapsulate_with_rng(kdf, key_len, &mut thread_rng())
+    }
+
+    pub fn encapsulate_with_rng<R: Rng + CryptoRng>(
+        &self,
+        kdf: Kdf,
+        key_len: usize,
+        rng: &mut R,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        let z = rng.gen_biguint_below(&self.n);
+        let ciphertext = to_padded_bytes(&z.modpow(&self.e, &self.n), modulus_bytes);
+        (ciphertext, derive_from(z, modulus_bytes, kdf, key_len))
+    }
+}
+
+impl RSA {
+    #[cfg(feature = "std")]
+    pub fn encapsulate(&self, kdf: Kdf, key_len: usize) -> (Vec<u8>, Vec<u8>) {
+        self.public().encapsulate(kdf, key_len)
+    }
+
+    pub fn encapsulate_with_rng<R: Rng + CryptoRng>(
+        &self,
+        kdf: Kdf,
+        key_len: usize,
+        rng: &mut R,
+    ) -> (Vec<u8>, Vec<u8>) {
+        self.public().encapsulate_with_rng(kdf, key_len, rng)
+    }
+
+    // `kdf` and `key_len` must match the sender's. The ciphertext has to be exactly
+    // the modulus size and below n, as ISO 18033-2 requires.
+    #[cfg(feature = "std")]
+    pub fn decapsulate(
+        &self,
+        ciphertext: &[u8],
+        kdf: Kdf,
+        key_len: usize,
+    ) -> Result<Vec<u8>, RsaError> {
+        self.decapsulate_with_rng(ciphertext, kdf, key_len, &mut thread_rng())
+    }
+
+    // `rng` supplies the blinding factor
+    pub fn decapsulate_with_rng<R: Rng + CryptoRng>(
+        &self,
+        ciphertext: &[u8],
+        kdf: Kdf,
+        key_len: usize,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let n = &self.public_key.1;
+        let modulus_bytes = modulus_byte_len(n);
+        if ciphertext.len() != modulus_bytes {
+            return Err(RsaError::InvalidCiphertextLength);
+        }
+        let c = BigUint::from_bytes_be(ciphertext);
+        if &c >= n {
+            return Err(RsaError::CiphertextOutOfRange);
+        }
+        let z = self.private_op(&c, rng);
+        Ok(derive_from(z, modulus_bytes, kdf, key_len))
+    }
+}
@@ -1,3 +1,54 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod aes;
+#[cfg(feature = "async")]
+mod async_keygen;
+mod audit;
+mod builder;
+mod ct;
+mod der;
+pub mod error;
+#[cfg(feature = "std")]
+mod framed;
+pub mod hash;
+#[cfg(feature = "std")]
+mod hybrid;
+mod kem;
+mod modpow;
+pub mod oaep;
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "std")]
+mod pbes2;
+mod pem;
+pub mod prime;
+pub mod pss;
 pub mod rsa;
+mod signer;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod wipe;
 
-pub use rsa::RSA;
\ No newline at end of file
+#[cfg(feature = "async")]
+pub use async_keygen::KeyGenFuture;
+pub use audit::KeyWarning;
+pub use builder::RsaKeyGenBuilder;
+pub use error::RsaError;
+pub use hash::{HashAlgorithm, mgf1};
+pub use kem::Kdf;
+pub use modpow::modpow_ct;
+pub use prime::{PrimeGenEvent, PrimeGenStats};
+pub use signer::{Signer, Verifier};
+pub use rsa::{
+    ByteOrder, CrtParams, MIN_KEY_BITS, OtherPrimeInfo, PaddingScheme, RSA, RSAPublicKey, extended_gcd, gcd,
+    rsa_decrypt,
+};
+#[cfg(feature = "std")]
+pub use hybrid::{Envelope, encrypt_to_recipients};
+#[cfg(feature = "std")]
+pub use rsa::rsa_encrypt;
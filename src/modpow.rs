@@ -0,0 +1,269 @@
+// Constant-time modular exponentiation for private-key operations.
+//
+// `BigUint::modpow` picks its work from the exponent's bits and trims leading zero
+// limbs as values shrink, so its running time depends on d. Here every value is
+// held in a fixed number of 64-bit limbs, multiplication is Montgomery (CIOS) with a
+// branch-free final subtraction, and the exponent is consumed in fixed 4-bit
+// windows: each window costs four squarings and one multiplication by a table
+// entry that is read by scanning the whole table. Which operations run, and which
+// memory they touch, depends only on the sizes of the modulus and the exponent.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::ct::ct_eq_u32;
+use crate::wipe::wipe_limbs;
+
+const WINDOW_BITS: usize = 4;
+
+// Computes `base^exponent mod modulus` with a running time that does not depend on
+// the exponent's value.
+//
+// The exponent is processed at the width of the modulus, or at its own width if
+// that is larger, so only those sizes show in the timing. The modulus must be odd,
+// as every RSA modulus and prime is; an even modulus falls back to
+// `BigUint::modpow`, which is not constant-time. Converting the base and the result
+// to and from `BigUint` is not hardened, but touches no exponent bits.
+pub fn modpow_ct(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+    if !modulus.bit(0) {
+        return base.modpow(exponent, modulus);
+    }
+    let ctx = Montgomery::new(modulus);
+
+    // table[i] = base^i in Montgomery form
+    let mut table = Vec::with_capacity(1 << WINDOW_BITS);
+    table.push(ctx.one.clone());
+    table.push(ctx.mul(&ctx.to_limbs(&(base % modulus)), &ctx.r2));
+    for i in 2..1 << WINDOW_BITS {
+        let next = ctx.mul(&table[i - 1], &table[1]);
+        table.push(next);
+    }
+
+    let bits = exponent.bits().max(modulus.bits()) as usize;
+    let windows = bits.div_ceil(WINDOW_BITS);
+    let mut exp = limbs(exponent, (windows * WINDOW_BITS).div_ceil(64));
+
+    let mut acc = ctx.one.clone();
+    for window in (0..windows).rev() {
+        for _ in 0..WINDOW_BITS {
+            acc = ctx.mul(&acc, &acc);
+        }
+        // Windows are aligned to 4 bits, so one never straddles two limbs
+        let offset = window * WINDOW_BITS;
+        let index = (exp[offset / 64] >> (offset % 64)) & ((1 << WINDOW_BITS) - 1);
+        acc = ctx.mul(&acc, &select(&table, index as u32));
+    }
+    wipe_limbs(&mut exp);
+
+    let mut unit = vec![0u64; ctx.n.len()];
+    unit[0] = 1;
+    from_limbs(&ctx.mul(&acc, &unit))
+}
+
+struct Montgomery {
+    n: Vec<u64>,
+    n0_inv: u64,   // -n^-1 mod 2^64
+    one: Vec<u64>, // R mod n, i.e. 1 in Montgomery form
+    r2: Vec<u64>,  // R^2 mod n, for converting into Montgomery form
+}
+
+impl Montgomery {
+    fn new(modulus: &BigUint) -> Self {
+        let len = modulus.iter_u64_digits().len();
+        let n = limbs(modulus, len);
+
+        // Newton's iteration doubles the number of correct low bits each step
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(n[0].wrapping_mul(inv)));
+        }
+
+        let r = BigUint::one() << (64 * len);
+        Montgomery {
+            one: limbs(&(&r % modulus), len),
+            r2: limbs(&((&r * &r) % modulus), len),
+            n,
+            n0_inv: inv.wrapping_neg(),
+        }
+    }
+
+    fn to_limbs(&self, x: &BigUint) -> Vec<u64> {
+        limbs(x, self.n.len())
+    }
+
+    // a * b * R^-1 mod n for a, b < n
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let len = self.n.len();
+        let mut t = vec![0u64; len + 2];
+        for &b_i in b {
+            // t += a * b_i
+            let mut carry = 0;
+            for (t_j, &a_j) in t.iter_mut().zip(a) {
+                (*t_j, carry) = mac(*t_j, a_j, b_i, carry);
+            }
+            let sum = u128::from(t[len]) + u128::from(carry);
+            t[len] = sum as u64;
+            t[len + 1] = (sum >> 64) as u64;
+
+            // t = (t + m * n) / 2^64, where m makes the low limb vanish
+            let m = t[0].wrapping_mul(self.n0_inv);
+            let (_, mut carry) = mac(t[0], m, self.n[0], 0);
+            for j in 1..len {
+                (t[j - 1], carry) = mac(t[j], m, self.n[j], carry);
+            }
+            let sum = u128::from(t[len]) + u128::from(carry);
+            t[len - 1] = sum as u64;
+            t[len] = t[len + 1] + (sum >> 64) as u64;
+        }
+
+        // t < 2n; subtract n unless that borrows out of the top limb
+        let mut reduced = vec![0u64; len];
+        let mut borrow = 0u64;
+        for j in 0..len {
+            let (diff, b1) = t[j].overflowing_sub(self.n[j]);
+            let (diff, b2) = diff.overflowing_sub(borrow);
+            reduced[j] = diff;
+            borrow = u64::from(b1 | b2);
+        }
+        let keep_reduced = (t[len] | (borrow ^ 1)).wrapping_neg();
+        for (r, &t_j) in reduced.iter_mut().zip(&t) {
+            *r = (*r & keep_reduced) | (t_j & !keep_reduced);
+        }
+        reduced
+    }
+}
+
+// t + a * b + carry, which cannot overflow 128 bits
+fn mac(t: u64, a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let wide = u128::from(t) + u128::from(a) * u128::from(b) + u128::from(carry);
+    (wide as u64, (wide >> 64) as u64)
+}
+
+// Reads every entry so the access pattern does not reveal `index`
+fn select(table: &[Vec<u64>], index: u32) -> Vec<u64> {
+    let mut out = vec![0u64; table[0].len()];
+    for (i, entry) in table.iter().enumerate() {
+        let mask = u64::from(ct_eq_u32(i as u32, index));
+        let mask = mask | (mask << 32);
+        for (o, &e) in out.iter_mut().zip(entry) {
+            *o |= e & mask;
+        }
+    }
+    out
+}
+
+// Little-endian limbs, zero-extended to `len`
+fn limbs(x: &BigUint, len: usize) -> Vec<u64> {
+    let mut out = vec![0u64; len];
+    for (o, digit) in out.iter_mut().zip(x.iter_u64_digits()) {
+        *o = digit;
+    }
+    out
+}
+
+fn from_limbs(limbs: &[u64]) -> BigUint {
+    BigUint::new(
+        limbs
+            .iter()
+            .flat_map(|&limb| [limb as u32, (limb >> 32) as u32])
+            .collect(),
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use num_bigint::RandBigInt;
+    use num_traits::Zero;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn odd_modulus(bits: u64, rng: &mut StdRng) -> BigUint {
+        let mut m = rng.gen_biguint(bits);
+        m.set_bit(bits - 1, true);
+        m.set_bit(0, true);
+        m
+    }
+
+    fn check(base: &BigUint, exponent: &BigUint, modulus: &BigUint) {
+        assert_eq!(
+            modpow_ct(base, exponent, modulus),
+            base.modpow(exponent, modulus),
+            "{base}^{exponent} mod {modulus}"
+        );
+    }
+
+    // One-limb, just-over-one-limb and multi-limb moduli, with bases and exponents
+    // below, at and above the modulus width
+    #[test]
+    fn matches_biguint_modpow() {
+        let mut rng = StdRng::seed_from_u64(67);
+        for bits in [2, 3, 8, 63, 64, 65, 127, 128, 129, 512, 1024, 2048] {
+            // The large sizes are slow unoptimised, and add no new code paths
+            let runs = if bits > 512 { 1 } else { 4 };
+            for _ in 0..runs {
+                let m = odd_modulus(bits, &mut rng);
+                let below = rng.gen_biguint_below(&m);
+                let above = rng.gen_biguint(bits + 70);
+                let short_exp = rng.gen_biguint(bits / 2 + 1);
+                let wide_exp = rng.gen_biguint(2 * bits + 5);
+                for base in [&below, &above, &m, &(&m + 1u32)] {
+                    for exponent in [&short_exp, &wide_exp, &(&m - 1u32)] {
+                        check(base, exponent, &m);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn edge_values() {
+        let mut rng = StdRng::seed_from_u64(6767);
+        let zero = BigUint::zero();
+        let one = BigUint::one();
+        for m in [
+            BigUint::from(3u32),
+            odd_modulus(64, &mut rng),
+            odd_modulus(300, &mut rng),
+        ] {
+            let base = rng.gen_biguint(400);
+            // x^0 = 1, 0^e = 0 (e > 0), 1^e = 1
+            assert_eq!(modpow_ct(&base, &zero, &m), one);
+            assert_eq!(modpow_ct(&zero, &zero, &m), one);
+            check(&zero, &base, &m);
+            check(&one, &base, &m);
+            check(&base, &one, &m);
+        }
+
+        // Everything is 0 mod 1, including x^0
+        let base = rng.gen_biguint(100);
+        for exponent in [&zero, &one, &base] {
+            assert_eq!(modpow_ct(&base, exponent, &one), zero);
+            check(&base, exponent, &one);
+        }
+    }
+
+    // Even moduli take the BigUint::modpow fallback, but must still agree with it
+    #[test]
+    fn even_modulus_fallback() {
+        let mut rng = StdRng::seed_from_u64(676767);
+        for bits in [2, 64, 65, 256, 1024] {
+            let mut m = odd_modulus(bits, &mut rng);
+            m.set_bit(0, false);
+            for _ in 0..4 {
+                let base = rng.gen_biguint(bits + 10);
+                let exponent = rng.gen_biguint(bits);
+                check(&base, &exponent, &m);
+            }
+        }
+        check(
+            &BigUint::from(7u32),
+            &BigUint::from(5u32),
+            &BigUint::from(2u32),
+        );
+    }
+}
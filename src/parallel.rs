@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::error::RsaError;
+use crate::prime::{is_prime, random_candidate, recommended_rounds};
+use crate::rsa::{MAX_KEYGEN_ATTEMPTS, RSA, check_key_size, prime_sizes, primes_far_apart};
+
+impl RSA {
+    // Searches for p and q concurrently, each across half of the available cores
+    pub fn new_parallel(bit_len: usize) -> Result<Self, RsaError> {
+        check_key_size(bit_len)?;
+        let e = BigUint::from(65537u32);
+        let workers = thread::available_parallelism().map_or(2, |n| n.get());
+        let per_prime = (workers / 2).max(1);
+        let (p_bits, q_bits) = prime_sizes(bit_len);
+
+        for _ in 0..MAX_KEYGEN_ATTEMPTS {
+            let (p, q) = thread::scope(|s| {
+                let p = s.spawn(|| parallel_prime(p_bits, per_prime));
+                let q = parallel_prime(q_bits, per_prime);
+                (p.join().expect("prime search thread panicked"), q)
+            });
+
+            if !primes_far_apart(&p, &q, q_bits) {
+                continue;
+            }
+            if let Some(rsa) = Self::from_primes(p, q, &e) {
+                return Ok(rsa);
+            }
+        }
+
+        Err(RsaError::NonInvertibleExponent)
+    }
+
+    // `decrypt` over every ciphertext, with the batch split into one contiguous run
+    // per core. Results are in input order, and one failure does not affect the rest.
+    pub fn decrypt_batch_parallel(&self, ciphertexts: &[&[u8]]) -> Vec<Result<Vec<u8>, RsaError>> {
+        let workers = thread::available_parallelism().map_or(2, |n| n.get());
+        let run = ciphertexts.len().div_ceil(workers).max(1);
+
+        thread::scope(|s| {
+            let runs: Vec<_> = ciphertexts
+                .chunks(run)
+                .map(|run| s.spawn(move || run.iter().map(|c| self.decrypt(c)).collect::<Vec<_>>()))
+                .collect();
+            runs.into_iter()
+                .flat_map(|run| run.join().expect("decryption thread panicked"))
+                .collect()
+        })
+    }
+}
+
+// Returns the first prime found by any worker; the rest stop at their next candidate
+fn parallel_prime(bit_length: usize, workers: usize) -> BigUint {
+    let found = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+
+    let rounds = recommended_rounds(bit_length);
+
+    thread::scope(|s| {
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let found = &found;
+            s.spawn(move || {
+                // Each worker seeds its own generator from the OS
+                let mut rng = StdRng::from_entropy();
+                while !found.load(Ordering::Relaxed) {
+                    let candidate = random_candidate(bit_length, &mut rng);
+                    if is_prime(&candidate, rounds, &mut rng) {
+                        found.store(true, Ordering::Relaxed);
+                        let _ = tx.send(candidate);
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        rx.recv().expect("all prime search workers exited")
+    })
+}
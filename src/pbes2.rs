@@ -0,0 +1,252 @@
+use rand::{Rng, thread_rng};
+
+use crate::aes::{BLOCK_LEN, KEY_LEN, cbc_decrypt, cbc_encrypt};
+use crate::der::{
+    DerReader, OID_AES256_CBC, OID_HMAC_SHA256, OID_PBES2, OID_PBKDF2, TAG_INTEGER, TAG_NULL,
+    TAG_OCTET_STRING, TAG_OID, TAG_SEQUENCE, encode_integer, encode_tlv,
+};
+use crate::error::RsaError;
+use crate::hash::Sha256;
+use crate::pem::{armor, dearmor, encode_pkcs8_private_key, parse_pkcs8_private_key};
+use crate::rsa::RSA;
+use crate::wipe::wipe_bytes;
+
+// Password-protected private keys: PKCS#8 EncryptedPrivateKeyInfo with PBES2
+// (RFC 8018), PBKDF2-HMAC-SHA256 and AES-256-CBC. This is what
+// `openssl pkcs8 -topk8 -v2 aes-256-cbc -v2prf hmacWithSHA256` produces.
+//
+// EncryptedPrivateKeyInfo ::= SEQUENCE {
+//     SEQUENCE { pbes2, SEQUENCE {
+//         SEQUENCE { pbkdf2, SEQUENCE { salt, iterations, prf } },
+//         SEQUENCE { aes256-CBC, iv } } },
+//     encryptedData OCTET STRING }
+
+const ENCRYPTED_PRIVATE_LABEL: &str = "ENCRYPTED PRIVATE KEY";
+
+// OWASP's 2023 recommendation for PBKDF2-HMAC-SHA256
+const PBKDF2_ITERATIONS: u32 = 600_000;
+// Keeps a hostile blob from pinning the CPU on import
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+
+impl RSA {
+    // Needs the primes, like `export_private_key_der`
+    pub fn export_private_key_encrypted(&self, password: &str) -> Result<String, RsaError> {
+        let mut pkcs1 = self.export_private_key_der()?;
+        let mut pkcs8 = encode_pkcs8_private_key(&pkcs1);
+        wipe_bytes(&mut pkcs1);
+
+        let mut rng = thread_rng();
+        let mut salt = [0u8; SALT_LEN];
+        let mut iv = [0u8; BLOCK_LEN];
+        rng.fill(&mut salt);
+        rng.fill(&mut iv);
+
+        let mut key = pbkdf2_hmac_sha256(password.as_bytes(), &salt, PBKDF2_ITERATIONS);
+        let encrypted = cbc_encrypt(&key, &iv, &pkcs8);
+        wipe_bytes(&mut key);
+        wipe_bytes(&mut pkcs8);
+
+        let mut prf = Vec::new();
+        encode_tlv(TAG_OID, OID_HMAC_SHA256, &mut prf);
+        encode_tlv(TAG_NULL, &[], &mut prf);
+        let mut kdf_params = Vec::new();
+        encode_tlv(TAG_OCTET_STRING, &salt, &mut kdf_params);
+        encode_integer(&PBKDF2_ITERATIONS.into(), &mut kdf_params);
+        encode_tlv(TAG_SEQUENCE, &prf, &mut kdf_params);
+        let mut kdf = Vec::new();
+        encode_tlv(TAG_OID, OID_PBKDF2, &mut kdf);
+        encode_tlv(TAG_SEQUENCE, &kdf_params, &mut kdf);
+
+        let mut scheme = Vec::new();
+        encode_tlv(TAG_OID, OID_AES256_CBC, &mut scheme);
+        encode_tlv(TAG_OCTET_STRING, &iv, &mut scheme);
+
+        let mut pbes2_params = Vec::new();
+        encode_tlv(TAG_SEQUENCE, &kdf, &mut pbes2_params);
+        encode_tlv(TAG_SEQUENCE, &scheme, &mut pbes2_params);
+        let mut algorithm = Vec::new();
+        encode_tlv(TAG_OID, OID_PBES2, &mut algorithm);
+        encode_tlv(TAG_SEQUENCE, &pbes2_params, &mut algorithm);
+
+        let mut content = Vec::new();
+        encode_tlv(TAG_SEQUENCE, &algorithm, &mut content);
+        encode_tlv(TAG_OCTET_STRING, &encrypted, &mut content);
+        let mut der = Vec::new();
+        encode_tlv(TAG_SEQUENCE, &content, &mut der);
+        Ok(armor(ENCRYPTED_PRIVATE_LABEL, &der))
+    }
+
+    // A wrong password fails with `DecryptionFailed`; the decrypted key is validated
+    pub fn import_private_key_encrypted(blob: &str, password: &str) -> Result<RSA, RsaError> {
+        let (label, der) = dearmor(blob)?;
+        if label != ENCRYPTED_PRIVATE_LABEL {
+            return Err(RsaError::InvalidPem);
+        }
+        let params = parse_encrypted_private_key(&der)?;
+
+        let mut key = pbkdf2_hmac_sha256(password.as_bytes(), params.salt, params.iterations);
+        let decrypted = cbc_decrypt(&key, &params.iv, params.encrypted);
+        wipe_bytes(&mut key);
+
+        // Garbage from a wrong password can still end in valid padding now and then
+        let mut pkcs8 = decrypted.ok_or(RsaError::DecryptionFailed)?;
+        let rsa = parse_pkcs8_private_key(&pkcs8).map_err(|_| RsaError::DecryptionFailed);
+        wipe_bytes(&mut pkcs8);
+        let rsa = rsa?;
+        rsa.validate()?;
+        Ok(rsa)
+    }
+}
+
+struct EncryptedKeyParams<'a> {
+    salt: &'a [u8],
+    iterations: u32,
+    iv: [u8; BLOCK_LEN],
+    encrypted: &'a [u8],
+}
+
+fn parse_encrypted_private_key(der: &[u8]) -> Result<EncryptedKeyParams<'_>, RsaError> {
+    let invalid = || RsaError::InvalidDer;
+    let expect_oid = |reader: &mut DerReader, oid: &[u8]| -> Result<(), RsaError> {
+        match reader.read_tlv(TAG_OID) {
+            Some(actual) if actual == oid => Ok(()),
+            Some(_) => Err(RsaError::UnsupportedAlgorithm),
+            None => Err(RsaError::InvalidDer),
+        }
+    };
+
+    let mut outer = DerReader::new(der);
+    let mut info = outer.read_sequence().ok_or_else(invalid)?;
+    let mut algorithm = info.read_sequence().ok_or_else(invalid)?;
+    expect_oid(&mut algorithm, OID_PBES2)?;
+    let mut pbes2_params = algorithm.read_sequence().ok_or_else(invalid)?;
+
+    let mut kdf = pbes2_params.read_sequence().ok_or_else(invalid)?;
+    expect_oid(&mut kdf, OID_PBKDF2)?;
+    let mut kdf_params = kdf.read_sequence().ok_or_else(invalid)?;
+    let salt = kdf_params.read_tlv(TAG_OCTET_STRING).ok_or_else(invalid)?;
+    let iterations = kdf_params
+        .read_integer()
+        .and_then(|n| u32::try_from(n).ok())
+        .filter(|n| (1..=MAX_PBKDF2_ITERATIONS).contains(n))
+        .ok_or_else(invalid)?;
+    // keyLength is optional, but has to match AES-256 when present
+    if kdf_params.peek_tag() == Some(TAG_INTEGER) {
+        let key_len = kdf_params.read_integer().ok_or_else(invalid)?;
+        if key_len != KEY_LEN.into() {
+            return Err(invalid());
+        }
+    }
+    // An absent prf means the default hmacWithSHA1, which we don't implement
+    let mut prf = kdf_params
+        .read_sequence()
+        .ok_or(RsaError::UnsupportedAlgorithm)?;
+    expect_oid(&mut prf, OID_HMAC_SHA256)?;
+
+    let mut scheme = pbes2_params.read_sequence().ok_or_else(invalid)?;
+    expect_oid(&mut scheme, OID_AES256_CBC)?;
+    let iv = scheme
+        .read_tlv(TAG_OCTET_STRING)
+        .and_then(|iv| iv.try_into().ok())
+        .ok_or_else(invalid)?;
+
+    let encrypted = info.read_tlv(TAG_OCTET_STRING).ok_or_else(invalid)?;
+    if !outer.is_empty() {
+        return Err(invalid());
+    }
+    Ok(EncryptedKeyParams {
+        salt,
+        iterations,
+        iv,
+        encrypted,
+    })
+}
+
+// PBKDF2 (RFC 8018, section 5.2) with HMAC-SHA256, producing one AES-256 key. A
+// single output block is enough since the key is exactly one digest long.
+fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; KEY_LEN] {
+    let hmac = Hmac::new(password);
+    let mut block = hmac.mac(&[salt, &1u32.to_be_bytes()]);
+    let mut out = block;
+    for _ in 1..iterations {
+        block = hmac.mac(&[&block]);
+        for (o, b) in out.iter_mut().zip(block) {
+            *o ^= b;
+        }
+    }
+    out
+}
+
+// HMAC-SHA256 (RFC 2104) with the keyed inner and outer states computed once, so
+// each PBKDF2 iteration costs four compressions instead of re-hashing the key
+struct Hmac {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl Hmac {
+    fn new(key: &[u8]) -> Self {
+        let mut block = [0u8; 64];
+        if key.len() > block.len() {
+            let mut hasher = Sha256::new();
+            hasher.update(key);
+            block[..32].copy_from_slice(&hasher.finalize());
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha256::new();
+        let mut outer = Sha256::new();
+        inner.update(&block.map(|b| b ^ 0x36));
+        outer.update(&block.map(|b| b ^ 0x5c));
+        wipe_bytes(&mut block);
+        Hmac { inner, outer }
+    }
+
+    fn mac(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut inner = self.inner.clone();
+        for part in parts {
+            inner.update(part);
+        }
+        let mut outer = self.outer.clone();
+        outer.update(&inner.finalize());
+        outer.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // RFC 7914, section 11. Those vectors ask for 64 bytes; the first block is
+    // the same whatever the output length, so it has to match our 32.
+    #[test]
+    fn pbkdf2_rfc7914_vectors() {
+        let cases: [(&str, &str, u32, &str); 2] = [
+            (
+                "passwd",
+                "salt",
+                1,
+                "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc",
+            ),
+            (
+                "Password",
+                "NaCl",
+                80_000,
+                "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56",
+            ),
+        ];
+        for (password, salt, iterations, expected) in cases {
+            let key = pbkdf2_hmac_sha256(password.as_bytes(), salt.as_bytes(), iterations);
+            assert_eq!(key.to_vec(), hex(expected), "{password}/{salt}");
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use base64::prelude::*;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+#[cfg(feature = "std")]
+use num_traits::Zero;
+
+use crate::ct::ct_base64_decode;
+use crate::der::{
+    DerReader, OID_RSA_ENCRYPTION, TAG_BIT_STRING, TAG_NULL, TAG_OCTET_STRING, TAG_OID,
+    TAG_SEQUENCE, encode_integer, encode_sequence, encode_tlv,
+};
+use crate::error::RsaError;
+use crate::rsa::{CrtParams, OtherPrimeInfo, RSA, RSAPublicKey};
+use crate::wipe::wipe_bytes;
+
+const PKCS1_PUBLIC_LABEL: &str = "RSA PUBLIC KEY";
+const PKCS1_PRIVATE_LABEL: &str = "RSA PRIVATE KEY";
+const PKCS8_PRIVATE_LABEL: &str = "PRIVATE KEY";
+const SPKI_PUBLIC_LABEL: &str = "PUBLIC KEY";
+
+impl RSAPublicKey {
+    // PKCS#1 RSAPublicKey ::= SEQUENCE { n, e }
+    pub fn export_public_key_der(&self) -> Vec<u8> {
+        encode_sequence(&[&self.n, &self.e])
+    }
+
+    pub fn export_public_key_pem(&self) -> String {
+        armor(PKCS1_PUBLIC_LABEL, &self.export_public_key_der())
+    }
+
+    // SubjectPublicKeyInfo ::= SEQUENCE { AlgorithmIdentifier, subjectPublicKey BIT STRING },
+    // the "BEGIN PUBLIC KEY" format used by X.509 and most generic tooling
+    pub fn export_public_key_spki_der(&self) -> Vec<u8> {
+        let mut algorithm = Vec::new();
+        encode_tlv(TAG_OID, OID_RSA_ENCRYPTION, &mut algorithm);
+        encode_tlv(TAG_NULL, &[], &mut algorithm);
+
+        // The leading zero is the BIT STRING's count of unused bits
+        let mut key_bits = vec![0x00];
+        key_bits.extend_from_slice(&self.export_public_key_der());
+
+        let mut content = Vec::new();
+        encode_tlv(TAG_SEQUENCE, &algorithm, &mut content);
+        encode_tlv(TAG_BIT_STRING, &key_bits, &mut content);
+        let mut out = Vec::new();
+        encode_tlv(TAG_SEQUENCE, &content, &mut out);
+        out
+    }
+
+    pub fn export_public_key_spki_pem(&self) -> String {
+        armor(SPKI_PUBLIC_LABEL, &self.export_public_key_spki_der())
+    }
+}
+
+impl RSA {
+    pub fn export_public_key_der(&self) -> Vec<u8> {
+        self.public().export_public_key_der()
+    }
+
+    pub fn export_public_key_pem(&self) -> String {
+        self.public().export_public_key_pem()
+    }
+
+    // Raw PKCS#1 RSAPublicKey or X.509 SubjectPublicKeyInfo DER, told apart by the
+    // first element: INTEGER n for PKCS#1, the AlgorithmIdentifier SEQUENCE for SPKI
+    pub fn import_public_key_der(der: &[u8]) -> Result<RSAPublicKey, RsaError> {
+        if first_inner_tag(der) == Some(TAG_SEQUENCE) {
+            return RSA::import_public_key_spki_der(der);
+        }
+        parse_pkcs1_public_key(der)
+    }
+
+    pub fn export_public_key_spki_der(&self) -> Vec<u8> {
+        self.public().export_public_key_spki_der()
+    }
+
+    pub fn export_public_key_spki_pem(&self) -> String {
+        self.public().export_public_key_spki_pem()
+    }
+
+    pub fn import_public_key_spki_der(der: &[u8]) -> Result<RSAPublicKey, RsaError> {
+        let mut outer = DerReader::new(der);
+        let mut info = outer.read_sequence().ok_or(RsaError::InvalidDer)?;
+
+        let mut algorithm = info.read_sequence().ok_or(RsaError::InvalidDer)?;
+        let oid = algorithm.read_tlv(TAG_OID).ok_or(RsaError::InvalidDer)?;
+        if oid != OID_RSA_ENCRYPTION {
+            return Err(RsaError::UnsupportedAlgorithm);
+        }
+        // The parameters must be NULL, though some encoders omit them entirely
+        if !algorithm.is_empty() {
+            let params = algorithm.read_tlv(TAG_NULL).ok_or(RsaError::InvalidDer)?;
+            if !params.is_empty() || !algorithm.is_empty() {
+                return Err(RsaError::InvalidDer);
+            }
+        }
+
+        let key_bits = info.read_tlv(TAG_BIT_STRING).ok_or(RsaError::InvalidDer)?;
+        if !info.is_empty() || !outer.is_empty() {
+            return Err(RsaError::InvalidDer);
+        }
+        match key_bits.split_first() {
+            Some((0x00, key)) => parse_pkcs1_public_key(key),
+            _ => Err(RsaError::InvalidDer),
+        }
+    }
+
+    pub fn import_public_key_spki_pem(pem: &str) -> Result<RSAPublicKey, RsaError> {
+        match dearmor(pem)? {
+            (label, der) if label == SPKI_PUBLIC_LABEL => RSA::import_public_key_spki_der(&der),
+            _ => Err(RsaError::InvalidPem),
+        }
+    }
+
+    // PKCS#1 RSAPrivateKey ::= SEQUENCE { version, n, e, d, p, q, dp, dq, qinv,
+    // otherPrimeInfos OPTIONAL }; multi-prime keys are written as version 1
+    pub fn export_private_key_der(&self) -> Result<Vec<u8>, RsaError> {
+        let crt = self.crt.as_ref().ok_or(RsaError::MissingPrimes)?;
+        let (e, n) = &self.public_key;
+        let d = &*self.private_exponent();
+        let version = BigUint::from(u8::from(!crt.other_primes.is_empty()));
+
+        let mut content = Vec::new();
+        for value in [&version, n, e, d, &crt.p, &crt.q, &crt.dp, &crt.dq, &crt.qinv] {
+            encode_integer(value, &mut content);
+        }
+        if !crt.other_primes.is_empty() {
+            let mut infos = Vec::new();
+            for other in &crt.other_primes {
+                infos.extend(encode_sequence(&[&other.r, &other.d, &other.t]));
+            }
+            encode_tlv(TAG_SEQUENCE, &infos, &mut content);
+        }
+        let mut out = Vec::new();
+        encode_tlv(TAG_SEQUENCE, &content, &mut out);
+        Ok(out)
+    }
+
+    pub fn export_private_key_pem(&self) -> Result<String, RsaError> {
+        Ok(armor(PKCS1_PRIVATE_LABEL, &self.export_private_key_der()?))
+    }
+
+    // Raw PKCS#1 RSAPrivateKey or unencrypted PKCS#8 PrivateKeyInfo DER. Both open
+    // with an INTEGER version; PKCS#8 follows it with the AlgorithmIdentifier SEQUENCE.
+    // The decoded key is validated before it is returned.
+    pub fn import_private_key_der(der: &[u8]) -> Result<RSA, RsaError> {
+        let rsa = if second_inner_tag(der) == Some(TAG_SEQUENCE) {
+            parse_pkcs8_private_key(der)?
+        } else {
+            parse_pkcs1_private_key(der)?
+        };
+        rsa.validate()?;
+        Ok(rsa)
+    }
+
+    // Accepts both PKCS#8 "PRIVATE KEY" and PKCS#1 "RSA PRIVATE KEY" blocks
+    pub fn import_private_key_pkcs8_pem(pem: &str) -> Result<RSA, RsaError> {
+        match dearmor(pem)? {
+            (label, der) if label == PKCS8_PRIVATE_LABEL || label == PKCS1_PRIVATE_LABEL => {
+                RSA::import_private_key_der(&der)
+            }
+            _ => Err(RsaError::InvalidPem),
+        }
+    }
+}
+
+// PrivateKeyInfo ::= SEQUENCE { version, AlgorithmIdentifier, privateKey OCTET STRING, ... }
+#[cfg(feature = "std")]
+pub(crate) fn encode_pkcs8_private_key(pkcs1: &[u8]) -> Vec<u8> {
+    let mut algorithm = Vec::new();
+    encode_tlv(TAG_OID, OID_RSA_ENCRYPTION, &mut algorithm);
+    encode_tlv(TAG_NULL, &[], &mut algorithm);
+
+    let mut content = Vec::new();
+    encode_integer(&BigUint::zero(), &mut content);
+    encode_tlv(TAG_SEQUENCE, &algorithm, &mut content);
+    encode_tlv(TAG_OCTET_STRING, pkcs1, &mut content);
+    let mut out = Vec::new();
+    encode_tlv(TAG_SEQUENCE, &content, &mut out);
+    out
+}
+
+pub(crate) fn parse_pkcs8_private_key(der: &[u8]) -> Result<RSA, RsaError> {
+    let mut outer = DerReader::new(der);
+    let mut info = outer.read_sequence().ok_or(RsaError::InvalidDer)?;
+    info.read_integer().ok_or(RsaError::InvalidDer)?;
+
+    let mut algorithm = info.read_sequence().ok_or(RsaError::InvalidDer)?;
+    let oid = algorithm.read_tlv(TAG_OID).ok_or(RsaError::InvalidDer)?;
+    if oid != OID_RSA_ENCRYPTION {
+        return Err(RsaError::UnsupportedAlgorithm);
+    }
+
+    let private_key = info.read_tlv(TAG_OCTET_STRING).ok_or(RsaError::InvalidDer)?;
+    // Optional attributes may follow inside PrivateKeyInfo, but nothing after it
+    if !outer.is_empty() {
+        return Err(RsaError::InvalidDer);
+    }
+    parse_pkcs1_private_key(private_key)
+}
+
+// Tags of the first two elements inside the outer SEQUENCE, for format detection
+fn first_inner_tag(der: &[u8]) -> Option<u8> {
+    DerReader::new(der).read_sequence()?.peek_tag()
+}
+
+fn second_inner_tag(der: &[u8]) -> Option<u8> {
+    let mut seq = DerReader::new(der).read_sequence()?;
+    seq.read_integer()?;
+    seq.peek_tag()
+}
+
+fn parse_pkcs1_public_key(der: &[u8]) -> Result<RSAPublicKey, RsaError> {
+    let mut outer = DerReader::new(der);
+    let mut seq = outer.read_sequence().ok_or(RsaError::InvalidDer)?;
+    let n = seq.read_integer().ok_or(RsaError::InvalidDer)?;
+    let e = seq.read_integer().ok_or(RsaError::InvalidDer)?;
+    if !seq.is_empty() || !outer.is_empty() {
+        return Err(RsaError::InvalidDer);
+    }
+    Ok(RSAPublicKey::new(e, n))
+}
+
+fn parse_pkcs1_private_key(der: &[u8]) -> Result<RSA, RsaError> {
+    let mut outer = DerReader::new(der);
+    let mut seq = outer.read_sequence().ok_or(RsaError::InvalidDer)?;
+
+    let mut next = || seq.read_integer().ok_or(RsaError::InvalidDer);
+    let version = next()?;
+    // Version 1 is multi-prime RSA and must carry at least one additional prime
+    let multi_prime = match version.to_u8() {
+        Some(0) => false,
+        Some(1) => true,
+        _ => return Err(RsaError::UnsupportedAlgorithm),
+    };
+    let n = next()?;
+    let e = next()?;
+    let d = next()?;
+    let mut crt = CrtParams {
+        p: next()?,
+        q: next()?,
+        dp: next()?,
+        dq: next()?,
+        qinv: next()?,
+        other_primes: Vec::new(),
+    };
+
+    if multi_prime {
+        let mut infos = seq.read_sequence().ok_or(RsaError::InvalidDer)?;
+        while !infos.is_empty() {
+            let mut info = infos.read_sequence().ok_or(RsaError::InvalidDer)?;
+            let mut next = || info.read_integer().ok_or(RsaError::InvalidDer);
+            crt.other_primes.push(OtherPrimeInfo {
+                r: next()?,
+                d: next()?,
+                t: next()?,
+            });
+        }
+        if crt.other_primes.is_empty() {
+            return Err(RsaError::InvalidDer);
+        }
+    }
+    if !seq.is_empty() || !outer.is_empty() {
+        return Err(RsaError::InvalidDer);
+    }
+
+    Ok(RSA {
+        public_key: (e, n.clone()),
+        private_key: (d, n),
+        crt: Some(crt),
+    })
+}
+
+pub(crate) fn armor(label: &str, der: &[u8]) -> String {
+    let encoded = BASE64_STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(core::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+// Returns the label of the first PEM block along with its decoded contents. The
+// body is usually a private key, so it is decoded with `ct_base64_decode`.
+pub(crate) fn dearmor(pem: &str) -> Result<(String, Vec<u8>), RsaError> {
+    let mut lines = pem.lines().map(str::trim);
+    let label = lines
+        .find_map(|line| line.strip_prefix("-----BEGIN ")?.strip_suffix("-----"))
+        .ok_or(RsaError::InvalidPem)?;
+    let end = format!("-----END {}-----", label);
+
+    let mut body = Vec::new();
+    for line in lines.by_ref() {
+        if line == end {
+            let der = ct_base64_decode(&body);
+            wipe_bytes(&mut body);
+            return Ok((label.to_string(), der.ok_or(RsaError::InvalidPem)?));
+        }
+        body.extend_from_slice(line.as_bytes());
+    }
+
+    wipe_bytes(&mut body);
+    Err(RsaError::InvalidPem)
+}
@@ -0,0 +1,976 @@
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{CheckedSub, One, ToPrimitive, Zero};
+use core::ops::ControlFlow;
+
+use rand::{CryptoRng, Rng};
+
+const SMALL_PRIME_COUNT: usize = 300;
+const SMALL_PRIMES: [u32; SMALL_PRIME_COUNT] = small_primes();
+const LARGEST_SMALL_PRIME: u32 = SMALL_PRIMES[SMALL_PRIME_COUNT - 1];
+
+// The first SMALL_PRIME_COUNT primes (up to 1987), computed at compile time
+const fn small_primes() -> [u32; SMALL_PRIME_COUNT] {
+    let mut primes = [0u32; SMALL_PRIME_COUNT];
+    let mut count = 0;
+    let mut candidate = 2u32;
+    while count < SMALL_PRIME_COUNT {
+        let mut is_prime = true;
+        let mut i = 0;
+        while i < count && primes[i] * primes[i] <= candidate {
+            if candidate.is_multiple_of(primes[i]) {
+                is_prime = false;
+                break;
+            }
+            i += 1;
+        }
+        if is_prime {
+            primes[count] = candidate;
+            count += 1;
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+// SMALL_PRIMES[start..end], with their product as a single u64
+struct PrimeGroup {
+    product: u64,
+    start: usize,
+    end: usize,
+}
+
+const GROUP_COUNT: usize = prime_groups_len();
+const PRIME_GROUPS: [PrimeGroup; GROUP_COUNT] = prime_groups();
+
+// Greedily packs consecutive small primes into u64 products. Shared by the two
+// functions below so the count and the groups always agree.
+const fn next_group_end(start: usize) -> (u64, usize) {
+    let mut product = 1u64;
+    let mut end = start;
+    while end < SMALL_PRIME_COUNT {
+        match product.checked_mul(SMALL_PRIMES[end] as u64) {
+            Some(next) => product = next,
+            None => break,
+        }
+        end += 1;
+    }
+    (product, end)
+}
+
+const fn prime_groups_len() -> usize {
+    let mut count = 0;
+    let mut start = 0;
+    while start < SMALL_PRIME_COUNT {
+        start = next_group_end(start).1;
+        count += 1;
+    }
+    count
+}
+
+const fn prime_groups() -> [PrimeGroup; GROUP_COUNT] {
+    let mut groups = [const {
+        PrimeGroup {
+            product: 0,
+            start: 0,
+            end: 0,
+        }
+    }; GROUP_COUNT];
+    let mut i = 0;
+    let mut start = 0;
+    while i < GROUP_COUNT {
+        let (product, end) = next_group_end(start);
+        groups[i] = PrimeGroup {
+            product,
+            start,
+            end,
+        };
+        start = end;
+        i += 1;
+    }
+    groups
+}
+
+// Below this bound, Miller-Rabin with the first thirteen primes as witnesses is
+// exact (Sorenson and Webster, 2015). The bases up to 37 alone only suffice below
+// 318,665,857,834,031,151,167,461, which is a strong pseudoprime to all of them.
+const DETERMINISTIC_BOUND: u128 = 3_317_044_064_679_887_385_961_981;
+const DETERMINISTIC_WITNESSES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+// Miller-Rabin primality test.
+//
+// Numbers below 3,317,044,064,679,887,385,961,981 are tested against a fixed
+// witness set, so the answer is exact and `k` and `rng` are unused. Above that,
+// `k` random witnesses are drawn from `rng` and a composite passes with
+// probability at most 2^-2k.
+pub fn is_prime<R: Rng + CryptoRng>(n: &BigUint, k: usize, rng: &mut R) -> bool {
+    if n <= &BigUint::one() {
+        return false;
+    }
+    if n <= &BigUint::from(3u32) {
+        return true;
+    }
+
+    if let Some(decided) = trial_division(n) {
+        return decided;
+    }
+
+    // The early returns above leave n >= 5 here; the checks below do not rely on it
+    let Some(n_minus_one) = n.checked_sub(&BigUint::one()) else {
+        return false;
+    };
+    let two = BigUint::from(2u32);
+    // 2 and 3 would fail the evenness check below, and [2, n - 2] would be empty
+    if n_minus_one <= two {
+        return !n_minus_one.is_zero();
+    }
+
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while &d % 2u32 == BigUint::zero() {
+        d /= 2u32;
+        s += 1;
+    }
+    // s == 0 means n - 1 is odd, i.e. n is even and therefore composite
+    if s == 0 {
+        return false;
+    }
+
+    if n < &BigUint::from(DETERMINISTIC_BOUND) {
+        return DETERMINISTIC_WITNESSES
+            .iter()
+            .all(|&a| passes_round(n, &n_minus_one, &d, s, &BigUint::from(a)));
+    }
+
+    (0..k).all(|_| {
+        let a = random_witness(&n_minus_one, rng);
+        passes_round(n, &n_minus_one, &d, s, &a)
+    })
+}
+
+// Witness a in [2, n - 2], for n - 1 >= 3; the upper bound of gen_biguint_range is
+// exclusive
+fn random_witness<R: Rng + CryptoRng>(n_minus_one: &BigUint, rng: &mut R) -> BigUint {
+    rng.gen_biguint_range(&BigUint::from(2u32), n_minus_one)
+}
+
+// One Miller-Rabin round with witness `a`, where n - 1 = d * 2^s and s >= 1
+fn passes_round(n: &BigUint, n_minus_one: &BigUint, d: &BigUint, s: usize, a: &BigUint) -> bool {
+    let two = BigUint::from(2u32);
+    let mut x = a.modpow(d, n);
+    if x.is_one() || &x == n_minus_one {
+        return true;
+    }
+
+    // Square up to s - 1 times; written as 1..s so the bound cannot underflow
+    for _ in 1..s {
+        x = x.modpow(&two, n);
+        if &x == n_minus_one {
+            return true;
+        }
+    }
+    false
+}
+
+// Cheap trial division rejects most composites before any modpow. Returns None
+// when n has no small factor and is too large for that to prove it prime.
+// n must be at least 2.
+fn trial_division(n: &BigUint) -> Option<bool> {
+    // Below the square of the largest small prime, trial division is a full proof
+    if let Some(small) = n.to_u64() {
+        let bound = LARGEST_SMALL_PRIME as u64 * LARGEST_SMALL_PRIME as u64;
+        if small < bound {
+            let has_factor = SMALL_PRIMES
+                .iter()
+                .map(|&p| p as u64)
+                .take_while(|&p| p * p <= small)
+                .any(|p| small.is_multiple_of(p));
+            return Some(!has_factor);
+        }
+    }
+
+    // One big-number reduction per group of primes, then plain u64 arithmetic
+    for group in PRIME_GROUPS.iter() {
+        let residue = (n % group.product).to_u64().unwrap_or(0);
+        let primes = &SMALL_PRIMES[group.start..group.end];
+        if primes.iter().any(|&p| residue.is_multiple_of(p as u64)) {
+            return Some(false);
+        }
+    }
+    None
+}
+
+// Baillie-PSW primality test: a base-2 Miller-Rabin round followed by a strong
+// Lucas probable prime test with Selfridge's parameters.
+//
+// Deterministic, and no composite is known to pass it. Slower than a couple of
+// Miller-Rabin rounds, but does not depend on the quality of an RNG.
+pub fn is_prime_bpsw(n: &BigUint) -> bool {
+    if n <= &BigUint::one() {
+        return false;
+    }
+    if let Some(decided) = trial_division(n) {
+        return decided;
+    }
+
+    // Past trial division n is odd and well above 3, but 0 to 3 are still handled
+    let Some(n_minus_one) = n.checked_sub(&BigUint::one()) else {
+        return false;
+    };
+    if n_minus_one <= BigUint::from(2u32) {
+        return !n_minus_one.is_zero();
+    }
+    let s = n_minus_one.trailing_zeros().unwrap_or(0) as usize;
+    let d = &n_minus_one >> s;
+    if !passes_round(n, &n_minus_one, &d, s, &BigUint::from(2u32)) {
+        return false;
+    }
+
+    // Selfridge's search below never ends for perfect squares
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return false;
+    }
+    is_strong_lucas_prp(n)
+}
+
+// Strong Lucas probable prime test on its own, with Selfridge's parameters.
+//
+// Uses no randomness, and its pseudoprimes are unrelated to those of
+// Miller-Rabin, so it checks the Miller-Rabin result independently of the
+// RNG that picked the bases. Some composites pass it alone (5459 = 53 * 103 is the
+// first); combined with Miller-Rabin, as in `is_prime_with_lucas`, none is known.
+pub fn is_lucas_prime(n: &BigUint) -> bool {
+    if n <= &BigUint::one() {
+        return false;
+    }
+    if !n.bit(0) {
+        return n == &BigUint::from(2u32);
+    }
+    // A small prime can equal one of the D values tried below
+    if let Some(small) = n.to_u32()
+        && SMALL_PRIMES.binary_search(&small).is_ok()
+    {
+        return true;
+    }
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return false;
+    }
+    is_strong_lucas_prp(n)
+}
+
+// `k` Miller-Rabin rounds followed by `is_lucas_prime`; a candidate has to pass both.
+pub fn is_prime_with_lucas<R: Rng + CryptoRng>(n: &BigUint, k: usize, rng: &mut R) -> bool {
+    is_prime(n, k, rng) && is_lucas_prime(n)
+}
+
+// Strong Lucas probable prime test with P = 1 and Q = (1 - D) / 4, where D is the
+// first of 5, -7, 9, -11, ... with Jacobi symbol (D / n) = -1. n must be odd,
+// not a perfect square, and not divisible by any small prime.
+fn is_strong_lucas_prp(n: &BigUint) -> bool {
+    let mut d_abs = 5u64;
+    let mut negative = false;
+    let d = loop {
+        let d = signed_mod(d_abs, negative, n);
+        match jacobi(&d, n) {
+            -1 => break d,
+            // A shared factor with some |D| < n means n is composite
+            0 => return false,
+            _ => {}
+        }
+        d_abs += 2;
+        negative = !negative;
+    };
+    // Q = (1 - D) / 4, with D = +-d_abs
+    let q = if negative {
+        // D = -d_abs, so Q = (1 + d_abs) / 4 > 0
+        BigUint::from((1 + d_abs) / 4) % n
+    } else {
+        signed_mod((d_abs - 1) / 4, true, n)
+    };
+
+    // n + 1 = k * 2^s with k odd
+    let n_plus_one = n + 1u32;
+    let s = n_plus_one.trailing_zeros().unwrap_or(0) as usize;
+    let k = &n_plus_one >> s;
+
+    // Left-to-right ladder over the bits of k, starting from U_1 = 1, V_1 = P = 1
+    let mut u = BigUint::one();
+    let mut v = BigUint::one();
+    let mut q_k = q.clone();
+    for bit in (0..k.bits() - 1).rev() {
+        // U_2j = U_j * V_j, V_2j = V_j^2 - 2 Q^j
+        u = &u * &v % n;
+        v = sub_mod(&(&v * &v % n), &(&q_k * 2u32 % n), n);
+        q_k = &q_k * &q_k % n;
+        if k.bit(bit) {
+            // U_2j+1 = (U_2j + V_2j) / 2, V_2j+1 = (D * U_2j + V_2j) / 2
+            let next_u = half_mod(&((&u + &v) % n), n);
+            v = half_mod(&((&d * &u + &v) % n), n);
+            u = next_u;
+            q_k = &q_k * &q % n;
+        }
+    }
+
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub_mod(&(&v * &v % n), &(&q_k * 2u32 % n), n);
+        if v.is_zero() {
+            return true;
+        }
+        q_k = &q_k * &q_k % n;
+    }
+    false
+}
+
+// Jacobi symbol (a / n) for odd n
+fn jacobi(a: &BigUint, n: &BigUint) -> i32 {
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        let twos = a.trailing_zeros().unwrap_or(0);
+        a >>= twos;
+        // (2 / n) = -1 exactly when n = 3 or 5 (mod 8)
+        let n_mod_8 = (&n % 8u32).to_u32_digits().first().copied().unwrap_or(0);
+        if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            result = -result;
+        }
+        // Quadratic reciprocity flips the sign when both are 3 (mod 4)
+        core::mem::swap(&mut a, &mut n);
+        if a.bit(1) && n.bit(1) {
+            result = -result;
+        }
+        a %= &n;
+    }
+    if n.is_one() { result } else { 0 }
+}
+
+// +-value reduced into [0, n)
+fn signed_mod(value: u64, negative: bool, n: &BigUint) -> BigUint {
+    let value = BigUint::from(value) % n;
+    if negative && !value.is_zero() {
+        n - value
+    } else {
+        value
+    }
+}
+
+fn sub_mod(a: &BigUint, b: &BigUint, n: &BigUint) -> BigUint {
+    if a >= b { a - b } else { n - b + a }
+}
+
+// x / 2 mod n for odd n
+fn half_mod(x: &BigUint, n: &BigUint) -> BigUint {
+    if x.bit(0) { (x + n) >> 1 } else { x >> 1 }
+}
+
+pub fn generate_prime<R: Rng + CryptoRng>(bit_length: usize, rng: &mut R) -> BigUint {
+    generate_prime_with_rounds(bit_length, recommended_rounds(bit_length), rng)
+}
+
+// An endless stream of random `bit_length`-bit primes, each from `generate_prime`.
+//
+// The primes are independent draws, so a small `bit_length` will repeat them.
+// Panics if `bit_length` is below 2, since there is no 0- or 1-bit prime.
+#[cfg(feature = "std")]
+pub fn prime_iter(bit_length: usize) -> impl Iterator<Item = BigUint> {
+    prime_iter_with_rng(bit_length, rand::thread_rng())
+}
+
+// `prime_iter` drawing from `rng`, which may also be a `&mut` borrow.
+pub fn prime_iter_with_rng<R: Rng + CryptoRng>(
+    bit_length: usize,
+    mut rng: R,
+) -> impl Iterator<Item = BigUint> {
+    assert!(bit_length >= 2, "no prime has fewer than 2 bits");
+    core::iter::repeat_with(move || generate_prime(bit_length, &mut rng))
+}
+
+// Generates a random `bit_length`-bit prime using `k` Miller-Rabin rounds.
+//
+// Each round lets a composite through with probability at most 1/4, so `k`
+// rounds bound the error by 2^-2k. For random candidates the real error is far
+// smaller, and shrinks as the bit length grows.
+//
+// Candidates come from an incremental search: a random odd start, then the odd
+// numbers after it, sieved by small primes whose residues are carried along
+// rather than recomputed. This favours primes that follow a long gap, since
+// every start inside the gap leads to them. Restarting from a fresh random
+// point after `bit_length` steps caps how far a start can be from its prime,
+// which keeps the bias small; Brandt and Damgård (CRYPTO '92) show the entropy
+// loss stays low even for an uncapped search.
+//
+// Panics if `bit_length` is below 2, since there is no 0- or 1-bit prime. The
+// same holds for every generator here built on this search.
+pub fn generate_prime_with_rounds<R: Rng + CryptoRng>(
+    bit_length: usize,
+    k: usize,
+    rng: &mut R,
+) -> BigUint {
+    search_prime(bit_length, rng, |candidate, rng| {
+        if is_prime(candidate, k, rng) {
+            ControlFlow::Break(candidate.clone())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+}
+
+// Like `generate_prime_with_rounds`, but a candidate must also pass
+// `is_lucas_prime`, so a weak RNG choosing correlated Miller-Rabin bases is not
+// enough to let a composite through.
+pub fn generate_prime_with_lucas<R: Rng + CryptoRng>(
+    bit_length: usize,
+    k: usize,
+    rng: &mut R,
+) -> BigUint {
+    search_prime(bit_length, rng, |candidate, rng| {
+        if is_prime_with_lucas(candidate, k, rng) {
+            ControlFlow::Break(candidate.clone())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+}
+
+// Progress reports from `generate_prime_with_progress` and `RSA::new_with_progress`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrimeGenEvent {
+    // A candidate was rejected as composite.
+    CandidateTested,
+    // A candidate passed and is being returned.
+    PrimeFound,
+}
+
+// Like `generate_prime_with_rounds`, calling `on_event` after every candidate
+// that gets past the small-prime sieve.
+//
+// Returns `None` as soon as `on_event` returns `ControlFlow::Break`.
+pub fn generate_prime_with_progress<R, F>(
+    bit_length: usize,
+    k: usize,
+    rng: &mut R,
+    mut on_event: F,
+) -> Option<BigUint>
+where
+    R: Rng + CryptoRng,
+    F: FnMut(PrimeGenEvent) -> ControlFlow<()>,
+{
+    search_prime(bit_length, rng, |candidate, rng| {
+        let event = if is_prime(candidate, k, rng) {
+            PrimeGenEvent::PrimeFound
+        } else {
+            PrimeGenEvent::CandidateTested
+        };
+        if on_event(event).is_break() {
+            return ControlFlow::Break(None);
+        }
+        if event == PrimeGenEvent::PrimeFound {
+            return ControlFlow::Break(Some(candidate.clone()));
+        }
+        ControlFlow::Continue(())
+    })
+}
+
+// Counters from `generate_prime_instrumented`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrimeGenStats {
+    // Every candidate looked at, including the prime that was returned.
+    pub candidates_tested: u64,
+    // Candidates skipped by the small-prime sieve without a modpow.
+    pub sieve_rejections: u64,
+    // Candidates that got past the sieve and failed Miller-Rabin.
+    pub mr_rejections: u64,
+}
+
+// Like `generate_prime`, also counting how the candidates before the prime
+// were rejected. Meant for benchmarking and tuning the sieve.
+//
+// Below 32 bits there is no sieve, so `sieve_rejections` stays 0 and every
+// composite counts as a Miller-Rabin rejection. Candidates past the top of a
+// window, which are never tested, are not counted.
+pub fn generate_prime_instrumented<R: Rng + CryptoRng>(
+    bit_length: usize,
+    rng: &mut R,
+) -> (BigUint, PrimeGenStats) {
+    let k = recommended_rounds(bit_length);
+    let mut stats = PrimeGenStats::default();
+    let mut sieved = 0;
+    let prime = search_prime_with_sieved(bit_length, rng, &mut sieved, |candidate, rng| {
+        if is_prime(candidate, k, rng) {
+            ControlFlow::Break(candidate.clone())
+        } else {
+            stats.mr_rejections += 1;
+            ControlFlow::Continue(())
+        }
+    });
+    stats.sieve_rejections = sieved;
+    stats.candidates_tested = stats.sieve_rejections + stats.mr_rejections + 1;
+    (prime, stats)
+}
+
+// Like `generate_prime`, but candidates are accepted by `is_prime_bpsw`
+// instead of random-base Miller-Rabin rounds.
+pub fn generate_prime_bpsw<R: Rng + CryptoRng>(bit_length: usize, rng: &mut R) -> BigUint {
+    search_prime(bit_length, rng, |candidate, _| {
+        if is_prime_bpsw(candidate) {
+            ControlFlow::Break(candidate.clone())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })
+}
+
+// Below this size a candidate could be one of the sieving primes itself, so the
+// search falls back to drawing every candidate afresh
+const SIEVE_MIN_BITS: usize = 32;
+
+// Feeds candidates to `test` until it breaks, using a `SieveWindow` per random start
+fn search_prime<R, T, F>(bit_length: usize, rng: &mut R, test: F) -> T
+where
+    R: Rng + CryptoRng,
+    F: FnMut(&BigUint, &mut R) -> ControlFlow<T>,
+{
+    search_prime_with_sieved(bit_length, rng, &mut 0, test)
+}
+
+// `search_prime`, adding the number of sieved-out candidates to `sieved`
+fn search_prime_with_sieved<R, T, F>(
+    bit_length: usize,
+    rng: &mut R,
+    sieved: &mut u64,
+    mut test: F,
+) -> T
+where
+    R: Rng + CryptoRng,
+    F: FnMut(&BigUint, &mut R) -> ControlFlow<T>,
+{
+    // A 1-bit candidate is always 1, which would loop forever, and 0 bits underflows
+    assert!(bit_length >= 2, "no prime has fewer than 2 bits");
+    if bit_length < SIEVE_MIN_BITS {
+        loop {
+            let candidate = random_candidate(bit_length, rng);
+            if let ControlFlow::Break(found) = test(&candidate, rng) {
+                return found;
+            }
+        }
+    }
+
+    loop {
+        let mut window = SieveWindow::new(bit_length, rng);
+        while let Some(candidate) = window.next_candidate() {
+            if let ControlFlow::Break(found) = test(&candidate, rng) {
+                *sieved += window.rejected;
+                return found;
+            }
+        }
+        *sieved += window.rejected;
+    }
+}
+
+// start, start + 2, start + 4, ... for `bit_length` steps, skipping every number
+// with a small prime factor. `residues[i]` tracks the current number modulo
+// SMALL_PRIMES[i], so each step costs a few hundred additions and no division.
+struct SieveWindow {
+    start: BigUint,
+    offset: u32,
+    steps_left: usize,
+    bit_length: usize,
+    residues: [u32; SMALL_PRIME_COUNT],
+    // Numbers skipped so far for having a small prime factor
+    rejected: u64,
+}
+
+impl SieveWindow {
+    fn new<R: Rng + CryptoRng>(bit_length: usize, rng: &mut R) -> Self {
+        Self::starting_at(random_candidate(bit_length, rng), bit_length, bit_length)
+    }
+
+    // `start` must be odd
+    fn starting_at(start: BigUint, steps: usize, bit_length: usize) -> Self {
+        let mut residues = [0u32; SMALL_PRIME_COUNT];
+        for group in PRIME_GROUPS.iter() {
+            let residue = (&start % group.product).to_u64().unwrap_or(0);
+            for i in group.start..group.end {
+                residues[i] = (residue % SMALL_PRIMES[i] as u64) as u32;
+            }
+        }
+        SieveWindow {
+            start,
+            offset: 0,
+            steps_left: steps,
+            bit_length,
+            residues,
+            rejected: 0,
+        }
+    }
+
+    // None once the window is used up, or when the numbers outgrow bit_length
+    fn next_candidate(&mut self) -> Option<BigUint> {
+        while self.steps_left > 0 {
+            self.steps_left -= 1;
+            let survives = self.residues.iter().all(|&r| r != 0);
+            let offset = self.offset;
+
+            self.offset += 2;
+            for (r, &p) in self.residues.iter_mut().zip(SMALL_PRIMES.iter()) {
+                *r += 2;
+                if *r >= p {
+                    *r -= p;
+                }
+            }
+
+            if survives {
+                let candidate = &self.start + offset;
+                if candidate.bits() > self.bit_length as u64 {
+                    return None;
+                }
+                return Some(candidate);
+            }
+            self.rejected += 1;
+        }
+        None
+    }
+}
+
+// Generates a `bit_length`-bit safe prime `p = 2q + 1`, where `q` is also prime.
+//
+// Both `p` and `q` have to be prime, so far more candidates are needed than for
+// `generate_prime_with_rounds`: roughly a hundred times more at 512 bits, and
+// the factor grows linearly with the bit length.
+//
+// Panics if `bit_length` is below 3, or 4 or 5: with the top two bits set there
+// is no safe prime of those sizes, and the search would never end.
+pub fn generate_safe_prime_with_rounds<R: Rng + CryptoRng>(
+    bit_length: usize,
+    k: usize,
+    rng: &mut R,
+) -> BigUint {
+    assert!(
+        bit_length == 3 || bit_length >= 6,
+        "no {bit_length}-bit safe prime has its top two bits set"
+    );
+    loop {
+        // q has the top two bits set, so p = 2q + 1 has them too
+        let q = random_candidate(bit_length - 1, rng);
+        let p = (&q << 1u32) + 1u32;
+        // Sieving p first is cheap and rejects most pairs before any modpow
+        if trial_division(&p) == Some(false) {
+            continue;
+        }
+        if is_prime(&q, k, rng) && is_prime(&p, k, rng) {
+            return p;
+        }
+    }
+}
+
+// Miller-Rabin rounds used for a `bit_length`-bit prime of an RSA key, by
+// `generate_prime` and every key generator that is not given an explicit count.
+//
+// The count comes from the worst-case bound alone: a round passes a composite
+// with probability at most 1/4 (Rabin), so `k` rounds give 2^-2k whatever the
+// candidate. `k` is chosen to reach the security strength of the modulus the prime
+// belongs to (twice `bit_length`), as listed in NIST SP 800-57 Part 1, Table 2:
+//
+// | prime bits | modulus bits | strength | rounds |
+// |------------|--------------|----------|--------|
+// | < 1024     | < 2048       | 80       | 40     |
+// | 1024       | 2048         | 112      | 56     |
+// | 1536       | 3072         | 128      | 64     |
+// | 3840       | 7680         | 192      | 96     |
+// | 7680       | 15360        | 256      | 128    |
+//
+// For random candidates this is conservative: the average-case analysis behind
+// FIPS 186-4, Table C.2 needs far fewer rounds. Most composites fail the first
+// round, so the extra rounds are spent almost only on the prime that is returned.
+pub fn recommended_rounds(bit_length: usize) -> usize {
+    match bit_length {
+        0..1024 => 40,
+        1024..1536 => 56,
+        1536..3840 => 64,
+        3840..7680 => 96,
+        _ => 128,
+    }
+}
+
+pub(crate) fn random_candidate<R: Rng + CryptoRng>(bit_length: usize, rng: &mut R) -> BigUint {
+    let mut num = rng.gen_biguint(bit_length as u64);
+    num.set_bit((bit_length as u64) - 1, true); // Garante bit mais alto
+    if bit_length > 1 {
+        // E o segundo mais alto, para que p * q tenha exatamente a soma dos bits
+        num.set_bit((bit_length as u64) - 2, true);
+    }
+    num.set_bit(0, true);              // Garante que é ímpar
+    num
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    const LIMIT: usize = 100_000;
+
+    // Sieve of Eratosthenes: flags[n] is true iff n is prime, for n < limit
+    fn eratosthenes(limit: usize) -> Vec<bool> {
+        let mut flags = vec![true; limit];
+        flags[0] = false;
+        flags[1] = false;
+        let mut p = 2;
+        while p * p < limit {
+            if flags[p] {
+                for multiple in (p * p..limit).step_by(p) {
+                    flags[multiple] = false;
+                }
+            }
+            p += 1;
+        }
+        flags
+    }
+
+    #[test]
+    fn small_primes_match_eratosthenes() {
+        let flags = eratosthenes(LARGEST_SMALL_PRIME as usize + 1);
+        let expected: Vec<u32> = (0..flags.len() as u32)
+            .filter(|&n| flags[n as usize])
+            .collect();
+        assert_eq!(SMALL_PRIMES.to_vec(), expected);
+    }
+
+    #[test]
+    fn is_prime_matches_eratosthenes() {
+        let flags = eratosthenes(LIMIT);
+        let mut rng = rand::thread_rng();
+        for (n, &expected) in flags.iter().enumerate() {
+            assert_eq!(is_prime(&BigUint::from(n), 1, &mut rng), expected, "{n}");
+        }
+    }
+
+    // Below LARGEST_SMALL_PRIME^2 a number with no small prime factor is prime, so a
+    // window over the odd numbers past the sieving primes must yield exactly the primes
+    #[test]
+    fn sieve_window_matches_eratosthenes() {
+        let flags = eratosthenes(LIMIT);
+        let start = LARGEST_SMALL_PRIME as usize + 2;
+        let steps = (LIMIT - start).div_ceil(2);
+        let bit_length = (LIMIT as u64).ilog2() as usize + 1;
+        let mut window = SieveWindow::starting_at(BigUint::from(start), steps, bit_length);
+
+        let mut survivors = Vec::new();
+        while let Some(candidate) = window.next_candidate() {
+            survivors.push(candidate.to_usize().unwrap());
+        }
+        let expected: Vec<usize> = (start..LIMIT).filter(|&n| flags[n]).collect();
+        assert_eq!(survivors, expected);
+        // Every odd number in the window was either yielded or counted as rejected
+        assert_eq!(window.rejected as usize + survivors.len(), steps);
+    }
+
+    // The slow way: one big-number reduction per small prime
+    fn has_small_factor(n: &BigUint) -> bool {
+        SMALL_PRIMES.iter().any(|&p| (n % p).is_zero())
+    }
+
+    #[test]
+    fn grouped_trial_division_matches_naive() {
+        let mut rng = StdRng::seed_from_u64(49);
+        for bits in [32, 64, 65, 128, 512, 2048] {
+            for _ in 0..500 {
+                let n = random_candidate(bits, &mut rng);
+                let expected = has_small_factor(&n).then_some(false);
+                assert_eq!(trial_division(&n), expected, "{n}");
+            }
+            // Multiples of every small prime, so the last groups are hit as well
+            for &p in SMALL_PRIMES.iter() {
+                let n = random_candidate(bits, &mut rng) * p;
+                assert_eq!(trial_division(&n), Some(false), "{n} = {p} * ...");
+            }
+        }
+    }
+
+    #[test]
+    fn sieve_window_matches_naive() {
+        let mut rng = StdRng::seed_from_u64(4949);
+        for bits in [32, 64, 256, 1024] {
+            for _ in 0..5 {
+                let start = random_candidate(bits, &mut rng);
+                let steps = 2_000;
+                let mut window = SieveWindow::starting_at(start.clone(), steps, bits);
+
+                let survivors: Vec<BigUint> =
+                    core::iter::from_fn(|| window.next_candidate()).collect();
+                let expected: Vec<BigUint> = (0..steps as u32)
+                    .map(|i| &start + 2 * i)
+                    .filter(|n| n.bits() <= bits as u64)
+                    .filter(|n| !has_small_factor(n))
+                    .collect();
+                assert_eq!(survivors, expected, "{bits}-bit window from {start}");
+            }
+        }
+    }
+
+    // Every witness in [2, n - 2] gets drawn, n - 2 included, and nothing outside
+    #[test]
+    fn random_witnesses_cover_two_to_n_minus_two() {
+        let mut rng = StdRng::seed_from_u64(29);
+        for n in [5u32, 7, 11, 101] {
+            let mut seen = vec![false; n as usize];
+            for _ in 0..100 * n {
+                let a = random_witness(&BigUint::from(n - 1), &mut rng);
+                seen[a.to_usize().unwrap()] = true;
+            }
+            let expected: Vec<bool> = (0..n).map(|a| (2..=n - 2).contains(&a)).collect();
+            assert_eq!(seen, expected, "n = {n}");
+        }
+    }
+
+    // Above the deterministic bound the witnesses are random: 2^89 - 1 is prime, and
+    // the bound itself is a strong pseudoprime to every fixed witness
+    #[test]
+    fn random_witnesses_above_the_bound() {
+        let mut rng = StdRng::seed_from_u64(2929);
+        let mersenne = (BigUint::one() << 89u32) - 1u32;
+        let pseudoprime = BigUint::from(DETERMINISTIC_BOUND);
+        assert!(mersenne > pseudoprime);
+        assert!(
+            DETERMINISTIC_WITNESSES
+                .iter()
+                .all(|&a| strong_probable_prime(&pseudoprime, a))
+        );
+        for _ in 0..20 {
+            assert!(is_prime(&mersenne, 10, &mut rng));
+            assert!(!is_prime(&pseudoprime, 10, &mut rng));
+        }
+    }
+
+    fn strong_probable_prime(n: &BigUint, a: u32) -> bool {
+        let n_minus_one = n - 1u32;
+        let s = n_minus_one.trailing_zeros().unwrap() as usize;
+        let d = &n_minus_one >> s;
+        passes_round(n, &n_minus_one, &d, s, &BigUint::from(a))
+    }
+
+    // n = 3 mod 4 gives s = 1, where the squaring loop must run zero times: every
+    // witness of a prime passes on the first check, and composites fail
+    #[test]
+    fn single_factor_of_two() {
+        for n in [3u32, 7, 11, 19, 23, 15, 35, 39, 51] {
+            let n_big = BigUint::from(n);
+            let n_minus_one = BigUint::from(n - 1);
+            let d = BigUint::from((n - 1) / 2);
+            let passes = |a: u32| passes_round(&n_big, &n_minus_one, &d, 1, &BigUint::from(a));
+            let passing = (2..n).filter(|&a| passes(a)).count();
+            if [3, 7, 11, 19, 23].contains(&n) {
+                assert_eq!(passing, n as usize - 2, "{n}");
+            } else {
+                // a = n - 1 always passes, since (n - 1)^d = -1 for odd d
+                assert!(passing < n as usize - 2, "{n}");
+                assert!(!passes(2), "{n}");
+            }
+        }
+
+        // The same through is_prime, past trial division and the deterministic bound
+        let mut rng = StdRng::seed_from_u64(30);
+        let prime = BigUint::from(1_000_000_007u64);
+        let composite = &prime * 1_000_000_009u64;
+        let mersenne = (BigUint::one() << 89u32) - 1u32;
+        for (n, expected) in [(&prime, true), (&composite, false), (&mersenne, true)] {
+            assert_eq!(n % 4u32, BigUint::from(3u32));
+            assert_eq!(is_prime(n, 10, &mut rng), expected, "{n}");
+        }
+        for n in [3u32, 7, 11] {
+            assert!(is_prime(&BigUint::from(n), 1, &mut rng));
+        }
+    }
+
+    #[test]
+    fn bpsw_primes_match_eratosthenes() {
+        let flags = eratosthenes(LIMIT);
+        let mut rng = StdRng::seed_from_u64(42);
+        for bits in 2..=16 {
+            for _ in 0..20 {
+                let p = generate_prime_bpsw(bits, &mut rng);
+                assert_eq!(p.bits(), bits as u64);
+                assert!(flags[p.to_usize().unwrap()], "{p}");
+            }
+        }
+        let p = generate_prime_bpsw(256, &mut rng);
+        assert_eq!(p.bits(), 256);
+        assert!(is_prime(&p, 40, &mut rng));
+    }
+
+    #[test]
+    #[should_panic(expected = "no prime has fewer than 2 bits")]
+    fn bpsw_rejects_one_bit() {
+        generate_prime_bpsw(1, &mut StdRng::seed_from_u64(1));
+    }
+
+    #[test]
+    fn instrumented_counts_add_up() {
+        let flags = eratosthenes(LIMIT);
+        let mut rng = StdRng::seed_from_u64(97);
+        // Below the sieve every composite is a Miller-Rabin rejection
+        for _ in 0..20 {
+            let (p, stats) = generate_prime_instrumented(16, &mut rng);
+            assert!(flags[p.to_usize().unwrap()] && p.bits() == 16, "{p}");
+            assert_eq!(stats.sieve_rejections, 0);
+            assert_eq!(stats.candidates_tested, stats.mr_rejections + 1);
+        }
+
+        let mut sieved = 0;
+        for _ in 0..5 {
+            let (p, stats) = generate_prime_instrumented(512, &mut rng);
+            assert_eq!(p.bits(), 512);
+            assert!(is_prime(&p, 40, &mut rng));
+            assert_eq!(
+                stats.candidates_tested,
+                stats.sieve_rejections + stats.mr_rejections + 1
+            );
+            sieved += stats.sieve_rejections;
+        }
+        // Most 512-bit candidates have a small factor
+        assert!(sieved > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "no prime has fewer than 2 bits")]
+    fn instrumented_rejects_zero_bits() {
+        generate_prime_instrumented(0, &mut StdRng::seed_from_u64(0));
+    }
+
+    #[test]
+    fn progress_reports_every_candidate() {
+        let mut rng = StdRng::seed_from_u64(48);
+        let mut events = Vec::new();
+        let p = generate_prime_with_progress(256, 20, &mut rng, |event| {
+            events.push(event);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(p.bits(), 256);
+        assert!(is_prime(&p, 20, &mut rng));
+        assert_eq!(events.last(), Some(&PrimeGenEvent::PrimeFound));
+        let found = events
+            .iter()
+            .filter(|e| **e == PrimeGenEvent::PrimeFound)
+            .count();
+        assert_eq!(found, 1);
+    }
+
+    #[test]
+    fn progress_break_cancels_the_search() {
+        let mut rng = StdRng::seed_from_u64(48);
+        let mut calls = 0;
+        let p = generate_prime_with_progress(256, 20, &mut rng, |_| {
+            calls += 1;
+            ControlFlow::Break(())
+        });
+        assert!(p.is_none());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,101 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::ct::ct_eq;
+use crate::error::RsaError;
+use crate::hash::{HashAlgorithm, mgf1};
+use crate::oaep::xor_in_place;
+
+// M' = (0x)00 00 00 00 00 00 00 00 || mHash || salt
+fn message_prime_hash(m_hash: &[u8], salt: &[u8], hash: HashAlgorithm) -> Vec<u8> {
+    let mut m_prime = vec![0u8; 8];
+    m_prime.extend_from_slice(m_hash);
+    m_prime.extend_from_slice(salt);
+    hash.digest(&m_prime)
+}
+
+// Mask for the bits of EM[0] above em_bits, which must be zero
+fn top_byte_mask(em_len: usize, em_bits: usize) -> u8 {
+    0xFF >> (8 * em_len - em_bits)
+}
+
+// Fails unless a `salt_len`-byte salt fits beside the hash in `em_bits`; checked
+// before anything of that size is allocated
+pub(crate) fn check_salt_len(
+    em_bits: usize,
+    hash: HashAlgorithm,
+    salt_len: usize,
+) -> Result<(), RsaError> {
+    match em_bits.div_ceil(8).checked_sub(hash.output_len() + 2) {
+        Some(max) if salt_len <= max => Ok(()),
+        max => Err(RsaError::InvalidSaltLength {
+            got: salt_len,
+            max: max.unwrap_or(0),
+        }),
+    }
+}
+
+// EMSA-PSS encoding (RFC 8017, section 9.1.1). `em_bits` is modBits - 1.
+pub fn pss_encode(
+    message: &[u8],
+    em_bits: usize,
+    hash: HashAlgorithm,
+    salt: &[u8],
+) -> Result<Vec<u8>, RsaError> {
+    check_salt_len(em_bits, hash, salt.len())?;
+    let h_len = hash.output_len();
+    let em_len = em_bits.div_ceil(8);
+
+    let h = message_prime_hash(&hash.digest(message), salt, hash);
+
+    // DB = PS || 0x01 || salt
+    let db_len = em_len - h_len - 1;
+    let mut db = vec![0u8; db_len - salt.len() - 1];
+    db.push(0x01);
+    db.extend_from_slice(salt);
+    xor_in_place(&mut db, &mgf1(&h, db_len, hash));
+    db[0] &= top_byte_mask(em_len, em_bits);
+
+    let mut encoded = db;
+    encoded.extend_from_slice(&h);
+    encoded.push(0xbc);
+    Ok(encoded)
+}
+
+// EMSA-PSS verification (RFC 8017, section 9.1.2). `encoded` must be em_len bytes.
+pub fn pss_verify(
+    message: &[u8],
+    encoded: &[u8],
+    em_bits: usize,
+    hash: HashAlgorithm,
+    salt_len: usize,
+) -> bool {
+    let h_len = hash.output_len();
+    let em_len = em_bits.div_ceil(8);
+    if encoded.len() != em_len || check_salt_len(em_bits, hash, salt_len).is_err() {
+        return false;
+    }
+    if encoded[em_len - 1] != 0xbc {
+        return false;
+    }
+
+    let (masked_db, rest) = encoded.split_at(em_len - h_len - 1);
+    let h = &rest[..h_len];
+    let mask = top_byte_mask(em_len, em_bits);
+    if masked_db[0] & !mask != 0 {
+        return false;
+    }
+
+    let mut db = masked_db.to_vec();
+    xor_in_place(&mut db, &mgf1(h, masked_db.len(), hash));
+    db[0] &= mask;
+
+    // The padding must be all zeros followed by a single 0x01
+    let ps_len = db.len() - salt_len - 1;
+    if db[..ps_len].iter().any(|&b| b != 0x00) || db[ps_len] != 0x01 {
+        return false;
+    }
+
+    let salt = &db[ps_len + 1..];
+    ct_eq(&message_prime_hash(&hash.digest(message), salt, hash), h)
+}
@@ -1,191 +1,2052 @@
+use alloc::borrow::Cow;
+use alloc::{format, string::String, vec, vec::Vec};
+#[cfg(feature = "std")]
+use core::cell::Cell;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
+use core::str::FromStr;
+
 pub use num_bigint::{BigInt, BigUint, RandBigInt, ToBigInt};
 use num_traits::{One, Zero};
-use rand::{Rng, thread_rng};
+use rand::{CryptoRng, Rng};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+#[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use base64::prelude::*;
 
+#[cfg(feature = "std")]
+use crate::builder::RsaKeyGenBuilder;
+#[cfg(feature = "std")]
+use crate::ct::ct_base64_decode;
+use crate::ct::{ct_eq, ct_eq_u32, ct_is_zero, ct_lt, ct_select};
+use crate::der::encode_sequence;
+use crate::error::RsaError;
+use crate::hash::HashAlgorithm;
+#[cfg(not(feature = "fast-insecure"))]
+use crate::modpow::modpow_ct;
+#[cfg(feature = "std")]
+use crate::oaep::oaep_unpad;
+use crate::oaep::{oaep_decode, oaep_pad_with_rng};
+#[cfg(feature = "std")]
+use crate::prime::{PrimeGenEvent, generate_prime_with_progress};
+use crate::prime::{generate_prime_with_rounds, recommended_rounds};
+use crate::pss::{check_salt_len, pss_encode, pss_verify};
+use crate::wipe::{wipe_biguint, wipe_bytes};
+
+// Smallest modulus accepted by key generation and validation
+pub const MIN_KEY_BITS: usize = 512;
+
+// Number of prime pairs tried before giving up on finding an invertible exponent
+pub(crate) const MAX_KEYGEN_ATTEMPTS: usize = 16;
+
+// First byte of `RSA::to_bytes`, bumped whenever the layout changes
+const KEY_BYTES_VERSION: u8 = 1;
+
+// Most primes `RSA::to_bytes` writes and `RSA::from_bytes` reads, as for key generation
+const KEY_BYTES_MAX_PRIMES: usize = 5;
+
+// Public exponents `RSA::recover_public_key` tries, most common first
+pub const COMMON_EXPONENTS: [u32; 5] = [65537, 3, 5, 17, 257];
+
+// Encryption padding recovered by `RSA::decrypt_auto`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingScheme {
+    Pkcs1v15,
+    Oaep(HashAlgorithm),
+}
+
+// How key components are laid out as bytes. Everything in this crate, from the
+// JSON export to DER and ciphertexts, is big-endian; `LittleEndian` is only for
+// importing material from systems that store integers least significant byte first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    BigEndian,
+    LittleEndian,
+}
+
+impl ByteOrder {
+    pub fn to_biguint(self, bytes: &[u8]) -> BigUint {
+        match self {
+            ByteOrder::BigEndian => BigUint::from_bytes_be(bytes),
+            ByteOrder::LittleEndian => BigUint::from_bytes_le(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RSAPublicKeyExport {
     e: String,
     n: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct RSAPrivateKeyExport {
-    d: String,
-    n: String,
-}
+// (e, n) in, base64 components out; the JSON layer only ever sees the struct
+#[cfg(feature = "std")]
+impl From<&(BigUint, BigUint)> for RSAPublicKeyExport {
+    fn from((e, n): &(BigUint, BigUint)) -> Self {
+        RSAPublicKeyExport {
+            e: BASE64_STANDARD.encode(e.to_bytes_be()),
+            n: BASE64_STANDARD.encode(n.to_bytes_be()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<RSAPublicKeyExport> for (BigUint, BigUint) {
+    type Error = RsaError;
+
+    fn try_from(export: RSAPublicKeyExport) -> Result<Self, RsaError> {
+        export.decode(ByteOrder::BigEndian)
+    }
+}
+
+#[cfg(feature = "std")]
+impl RSAPublicKeyExport {
+    fn decode(&self, order: ByteOrder) -> Result<(BigUint, BigUint), RsaError> {
+        Ok((decode_component(&self.e, order)?, decode_component(&self.n, order)?))
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize)]
+pub struct RSAPrivateKeyExport {
+    // Absent from CRT-only exports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    d: Option<String>,
+    n: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    e: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    p: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dq: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    qinv: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    other_primes: Vec<OtherPrimeExport>,
+}
+
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize)]
+struct OtherPrimeExport {
+    r: String,
+    d: String,
+    t: String,
+}
+
+// Stands in for secret fields in Debug output, so keys can be logged safely
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for RSAPrivateKeyExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redact = |field: &Option<String>| field.as_ref().map(|_| Redacted);
+        f.debug_struct("RSAPrivateKeyExport")
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("d", &Redacted)
+            .field("p", &redact(&self.p))
+            .field("q", &redact(&self.q))
+            .field("dp", &redact(&self.dp))
+            .field("dq", &redact(&self.dq))
+            .field("qinv", &redact(&self.qinv))
+            .field("other_primes", &self.other_primes.iter().map(|_| Redacted).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+// Chinese Remainder Theorem parameters for fast private-key operations
+#[derive(Clone)]
+pub struct CrtParams {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub dp: BigUint,   // d mod (p - 1)
+    pub dq: BigUint,   // d mod (q - 1)
+    pub qinv: BigUint, // q^-1 mod p
+    pub other_primes: Vec<OtherPrimeInfo>, // third prime onwards, for multi-prime keys
+}
+
+// An extra prime r_i of a multi-prime key (RFC 8017, section 3.2)
+#[derive(Clone)]
+pub struct OtherPrimeInfo {
+    pub r: BigUint,
+    pub d: BigUint, // d mod (r - 1)
+    pub t: BigUint, // (p * q * r_3 * ... * r_(i-1))^-1 mod r
+}
+
+impl CrtParams {
+    // None if p or q is below 2, or q is not invertible modulo p
+    pub fn new(p: BigUint, q: BigUint, d: &BigUint) -> Option<Self> {
+        if p <= BigUint::one() || q <= BigUint::one() {
+            return None;
+        }
+        let dp = d % (&p - BigUint::one());
+        let dq = d % (&q - BigUint::one());
+        let qinv = ee_modular_inverse(&q, &p)?;
+        Some(CrtParams {
+            p,
+            q,
+            dp,
+            dq,
+            qinv,
+            other_primes: Vec::new(),
+        })
+    }
+
+    // Appends further primes, deriving each one's exponent and coefficient
+    pub fn with_other_primes(mut self, primes: Vec<BigUint>, d: &BigUint) -> Option<Self> {
+        let mut product = &self.p * &self.q;
+        for r in primes {
+            if r <= BigUint::one() {
+                return None;
+            }
+            let t = ee_modular_inverse(&(&product % &r), &r)?;
+            product *= &r;
+            let d = d % (&r - BigUint::one());
+            self.other_primes.push(OtherPrimeInfo { r, d, t });
+        }
+        Some(self)
+    }
+
+    // p, q and then the other primes, in order
+    pub fn primes(&self) -> impl Iterator<Item = &BigUint> {
+        [&self.p, &self.q]
+            .into_iter()
+            .chain(self.other_primes.iter().map(|other| &other.r))
+    }
+
+    // Euler's totient: the product of r - 1 over every prime r
+    pub fn phi(&self) -> Result<BigUint, RsaError> {
+        Ok(self.primes_minus_one()?.product())
+    }
+
+    // Carmichael's function: the lcm of r - 1 over every prime r, which divides phi
+    pub fn lambda(&self) -> Result<BigUint, RsaError> {
+        Ok(self
+            .primes_minus_one()?
+            .fold(BigUint::one(), |acc, r1| lcm(&acc, &r1)))
+    }
+
+    // r - 1 for every prime r, failing if any of them is 0 or 1
+    fn primes_minus_one(&self) -> Result<impl Iterator<Item = BigUint> + '_, RsaError> {
+        if self.primes().any(|r| r <= &BigUint::one()) {
+            return Err(RsaError::KeyValidationFailed("prime is out of range"));
+        }
+        Ok(self.primes().map(|r| r - BigUint::one()))
+    }
+}
+
+// Every field is secret
+impl fmt::Debug for CrtParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CrtParams").finish_non_exhaustive()
+    }
+}
+
+impl Drop for CrtParams {
+    fn drop(&mut self) {
+        for secret in [&mut self.p, &mut self.q, &mut self.dp, &mut self.dq, &mut self.qinv] {
+            wipe_biguint(secret);
+        }
+    }
+}
+
+impl Drop for OtherPrimeInfo {
+    fn drop(&mut self) {
+        for secret in [&mut self.r, &mut self.d, &mut self.t] {
+            wipe_biguint(secret);
+        }
+    }
+}
+
+// Public half of a key pair: enough to encrypt and verify, nothing more
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RSAPublicKey {
+    pub e: BigUint,
+    pub n: BigUint,
+}
+
+impl RSAPublicKey {
+    pub fn new(e: BigUint, n: BigUint) -> Self {
+        RSAPublicKey { e, n }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn export_public_key(&self) -> String {
+        let export = RSAPublicKeyExport::from(&(self.e.clone(), self.n.clone()));
+        serde_json::to_string(&export).unwrap()
+    }
+
+    pub fn modulus_bits(&self) -> u64 {
+        self.n.bits()
+    }
+
+    // Largest message `encrypt` accepts under PKCS#1 v1.5 padding
+    pub fn max_message_len(&self) -> usize {
+        modulus_byte_len(&self.n).saturating_sub(11)
+    }
+
+    // Exact size of every ciphertext under this key, whatever the padding
+    pub fn ciphertext_len(&self) -> usize {
+        modulus_byte_len(&self.n)
+    }
+
+    // Exact size of every PKCS#1 v1.5 or PSS signature that verifies under this key
+    pub fn signature_len(&self) -> usize {
+        modulus_byte_len(&self.n)
+    }
+
+    // SHA-256 of the PKCS#1 DER public key, as colon-separated hex
+    pub fn public_key_fingerprint(&self) -> String {
+        let digest = HashAlgorithm::Sha256.digest(&encode_sequence(&[&self.n, &self.e]));
+        digest
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    #[cfg(feature = "std")]
+    pub fn encrypt(&self, message: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.encrypt_with_rng(message, &mut thread_rng())
+    }
+
+    pub fn encrypt_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let mut out = Vec::new();
+        self.encrypt_into_with_rng(message, &mut out, rng)?;
+        Ok(out)
+    }
+
+    // Clears `out` and fills it with exactly the modulus size in ciphertext bytes, so
+    // one buffer can be reused across calls; `out` also holds the padding meanwhile
+    #[cfg(feature = "std")]
+    pub fn encrypt_into(&self, message: &[u8], out: &mut Vec<u8>) -> Result<(), RsaError> {
+        self.encrypt_into_with_rng(message, out, &mut thread_rng())
+    }
+
+    pub fn encrypt_into_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        out: &mut Vec<u8>,
+        rng: &mut R,
+    ) -> Result<(), RsaError> {
+        pkcs1_encrypt_into(&self.e, &self.n, message, out, rng)
+    }
+
+    // Hex in, hex out: `0x` prefix optional, any case accepted, lowercase emitted
+    #[cfg(feature = "std")]
+    pub fn encrypt_hex(&self, hex_message: &str) -> Result<String, RsaError> {
+        Ok(encode_hex(&self.encrypt(&decode_hex(hex_message)?)?))
+    }
+
+    // Encrypts each message separately with PKCS#1 v1.5, one ciphertext per input.
+    //
+    // The modulus size is computed once for the whole batch. There is no separate
+    // Montgomery precomputation: `modpow` already uses Montgomery multiplication
+    // for odd moduli. Messages are encrypted in order on the calling thread, and
+    // the whole batch fails if any message is too long.
+    #[cfg(feature = "std")]
+    pub fn encrypt_batch(&self, messages: &[&[u8]]) -> Result<Vec<Vec<u8>>, RsaError> {
+        self.encrypt_batch_with_rng(messages, &mut thread_rng())
+    }
+
+    pub fn encrypt_batch_with_rng<R: Rng + CryptoRng>(
+        &self,
+        messages: &[&[u8]],
+        rng: &mut R,
+    ) -> Result<Vec<Vec<u8>>, RsaError> {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        messages
+            .iter()
+            .map(|message| {
+                let padded = pkcs1_pad_with_rng(message, modulus_bytes, rng)?;
+                let c = BigUint::from_bytes_be(&padded).modpow(&self.e, &self.n);
+                Ok(to_padded_bytes(&c, modulus_bytes))
+            })
+            .collect()
+    }
+
+    // Encrypts a message of any length by splitting it into PKCS#1 v1.5 blocks.
+    //
+    // Each block is encrypted independently (ECB-like), so block boundaries and
+    // count are visible. Prefer hybrid encryption for bulk data.
+    #[cfg(feature = "std")]
+    pub fn encrypt_chunked(&self, message: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        let block_len = self.max_message_len();
+        if block_len == 0 {
+            return Err(RsaError::MessageTooLong { got: message.len(), max: 0 });
+        }
+
+        let mut ciphertext = Vec::with_capacity(message.len().div_ceil(block_len) * modulus_bytes);
+        for block in message.chunks(block_len) {
+            ciphertext.extend_from_slice(&self.encrypt(block)?);
+        }
+        Ok(ciphertext)
+    }
+
+    // `label` is associated data: only its hash goes into the padding, and
+    // `decrypt_oaep` fails unless it is given the same label
+    #[cfg(feature = "std")]
+    pub fn encrypt_oaep(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        label: &[u8],
+    ) -> Result<Vec<u8>, RsaError> {
+        self.encrypt_oaep_with_rng(message, hash, label, &mut thread_rng())
+    }
+
+    // `rng` supplies the OAEP seed
+    pub fn encrypt_oaep_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        let padded = oaep_pad_with_rng(message, modulus_bytes, hash, label, rng)?;
+
+        let c = BigUint::from_bytes_be(&padded).modpow(&self.e, &self.n);
+        Ok(to_padded_bytes(&c, modulus_bytes))
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &[u8], hash: HashAlgorithm) -> bool {
+        self.verify_digest(&hash.digest(message), signature, hash)
+    }
+
+    pub(crate) fn verify_digest(
+        &self,
+        digest: &[u8],
+        signature: &[u8],
+        hash: HashAlgorithm,
+    ) -> bool {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        if signature.len() != modulus_bytes {
+            return false;
+        }
+
+        let Ok(expected) = emsa_pkcs1_v15_encode(digest, modulus_bytes, hash) else {
+            return false;
+        };
+        let Ok(recovered) = self.public_op(signature) else {
+            return false;
+        };
+
+        // Re-encode and compare the whole block so no part of the padding goes unchecked
+        ct_eq(&recovered, &expected)
+    }
+
+    // Raw public-key transform `s^e mod n`, left-padded to the modulus size. Recovers
+    // the padded block of a signature, e.g. to inspect a legacy DigestInfo by hand.
+    pub fn public_op(&self, data: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        if data.len() > modulus_bytes {
+            return Err(RsaError::InvalidCiphertextLength);
+        }
+        let s = BigUint::from_bytes_be(data);
+        if s >= self.n {
+            return Err(RsaError::CiphertextOutOfRange);
+        }
+        Ok(to_padded_bytes(&s.modpow(&self.e, &self.n), modulus_bytes))
+    }
+
+    // Textbook RSA: `m^e mod n` with no padding.
+    //
+    // **Not for real use.** Without padding, encryption is deterministic and
+    // malleable (`E(a) * E(b) = E(a * b)`). This exists for conformance test
+    // vectors and teaching only. `m` should be below `n`.
+    pub fn encrypt_raw(&self, m: &BigUint) -> BigUint {
+        m.modpow(&self.e, &self.n)
+    }
+
+    // PKCS#1 v1.5 encryption with the padding string supplied by the caller,
+    // giving reproducible ciphertexts for golden-file tests.
+    //
+    // **Test and debugging use only.** PKCS#1 v1.5 is secure only with fresh
+    // random padding for every message; with fixed padding, equal messages give
+    // equal ciphertexts. `padding` must be exactly the `modulus_bytes - 3 -
+    // message.len()` bytes that `encrypt` would draw (at least 8, none of them zero),
+    // otherwise this fails with `InvalidPadding`. `decrypt` reads the result as usual.
+    pub fn encrypt_with_padding_bytes(
+        &self,
+        message: &[u8],
+        padding: &[u8],
+    ) -> Result<Vec<u8>, RsaError> {
+        let modulus_bytes = modulus_byte_len(&self.n);
+        let max = self.max_message_len();
+        if modulus_bytes < 11 || message.len() > max {
+            return Err(RsaError::MessageTooLong {
+                got: message.len(),
+                max,
+            });
+        }
+        if padding.len() != modulus_bytes - 3 - message.len() || padding.contains(&0) {
+            return Err(RsaError::InvalidPadding);
+        }
+
+        let mut block = Vec::with_capacity(modulus_bytes);
+        block.extend_from_slice(&[0x00, 0x02]);
+        block.extend_from_slice(padding);
+        block.push(0x00);
+        block.extend_from_slice(message);
+        let c = BigUint::from_bytes_be(&block).modpow(&self.e, &self.n);
+        Ok(to_padded_bytes(&c, modulus_bytes))
+    }
+
+    pub fn verify_pss(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        hash: HashAlgorithm,
+        salt_len: usize,
+    ) -> bool {
+        let (e, n) = (&self.e, &self.n);
+        let modulus_bytes = modulus_byte_len(n);
+        if signature.len() != modulus_bytes {
+            return false;
+        }
+
+        let s = BigUint::from_bytes_be(signature);
+        if &s >= n {
+            return false;
+        }
+        let m = s.modpow(e, n);
+        let em_bits = (n.bits() - 1) as usize;
+        let em_len = em_bits.div_ceil(8);
+        if m.bits() > em_bits as u64 {
+            return false;
+        }
+        let encoded = left_pad(&m.to_bytes_be(), em_len);
+        pss_verify(message, &encoded, em_bits, hash, salt_len)
+    }
+}
+
+// A CRT-only key (see `RSA::from_crt_components`) stores d as zero and does every
+// private operation through `crt`; `private_exponent` recovers d when needed.
+#[derive(Clone)]
+pub struct RSA {
+    pub public_key: (BigUint, BigUint),  // (e, n)
+    pub private_key: (BigUint, BigUint),     // (d, n)
+    pub crt: Option<CrtParams>,
+}
+
+impl fmt::Debug for RSA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (e, n) = &self.public_key;
+        f.debug_struct("RSA")
+            .field("e", e)
+            .field("n", n)
+            .field("d", &Redacted)
+            .field("crt", &self.crt)
+            .finish()
+    }
+}
+
+// The private exponent is wiped here; CRT parameters wipe themselves
+impl Drop for RSA {
+    fn drop(&mut self) {
+        wipe_biguint(&mut self.private_key.0);
+    }
+}
+
+// Plain comparison of the key tuples: fast, but its timing depends on where the
+// private exponents first differ. Use `ct_eq` when either key may be attacker-chosen.
+impl PartialEq for RSA {
+    fn eq(&self, other: &Self) -> bool {
+        self.public_key == other.public_key
+            && self.private_key.1 == other.private_key.1
+            && self.private_exponent() == other.private_exponent()
+    }
+}
+
+impl Eq for RSA {}
+
+// Same field layout as `export_private_key`, so any serde format works
+#[cfg(feature = "std")]
+impl Serialize for RSA {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_export().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for RSA {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parsed = RSAPrivateKeyExport::deserialize(deserializer)?;
+        RSA::from_export(&parsed, ByteOrder::BigEndian).map_err(serde::de::Error::custom)
+    }
+}
+
+// `"...".parse::<RSAPublicKey>()` and friends, over the JSON from `export_public_key`
+#[cfg(feature = "std")]
+impl FromStr for RSAPublicKey {
+    type Err = RsaError;
+
+    fn from_str(json: &str) -> Result<Self, RsaError> {
+        RSA::import_public_key(json)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&str> for RSAPublicKey {
+    type Error = RsaError;
+
+    fn try_from(json: &str) -> Result<Self, RsaError> {
+        json.parse()
+    }
+}
+
+// Private keys parse the JSON from `export_private_key`, validated like
+// `import_private_key_checked`
+#[cfg(feature = "std")]
+impl FromStr for RSA {
+    type Err = RsaError;
+
+    fn from_str(json: &str) -> Result<Self, RsaError> {
+        RSA::import_private_key_checked(json)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&str> for RSA {
+    type Error = RsaError;
+
+    fn try_from(json: &str) -> Result<Self, RsaError> {
+        json.parse()
+    }
+}
+
+impl RSA {
+    #[cfg(feature = "std")]
+    pub fn export_private_key(&self) -> String {
+        serde_json::to_string(&self.to_export()).unwrap()
+    }
+
+    // `export_private_key` without d, for storage that keeps only the CRT parameters.
+    // `import_private_key_checked` reads it back as a CRT-only key.
+    #[cfg(feature = "std")]
+    pub fn export_private_key_crt_only(&self) -> Result<String, RsaError> {
+        if self.crt.is_none() {
+            return Err(RsaError::MissingPrimes);
+        }
+        let mut export = self.to_export();
+        export.d = None;
+        Ok(serde_json::to_string(&export).unwrap())
+    }
+
+    // Components are read as big-endian, the order `export_public_key` writes
+    #[cfg(feature = "std")]
+    pub fn import_public_key(json: &str) -> Result<RSAPublicKey, RsaError> {
+        Self::import_public_key_with_byte_order(json, ByteOrder::BigEndian)
+    }
+
+    // For JSON whose base64 components hold little-endian integers. A byte order
+    // mismatch turns the modulus's low byte into its high byte, so an even modulus
+    // is rejected rather than handed back as a silently wrong key.
+    #[cfg(feature = "std")]
+    pub fn import_public_key_with_byte_order(
+        json: &str,
+        order: ByteOrder,
+    ) -> Result<RSAPublicKey, RsaError> {
+        let parsed: RSAPublicKeyExport = serde_json::from_str(json)?;
+        let (e, n) = parsed.decode(order)?;
+        if !n.bit(0) {
+            return Err(RsaError::KeyValidationFailed(
+                "modulus is even; check the byte order",
+            ));
+        }
+        Ok(RSAPublicKey::new(e, n))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn import_private_key(json: &str) -> Result<(BigUint, BigUint), RsaError> {
+        let parsed: RSAPrivateKeyExport = serde_json::from_str(json)?;
+        let Some(d) = &parsed.d else {
+            // CRT-only: d has to be rebuilt from a validated key
+            let rsa = Self::from_export(&parsed, ByteOrder::BigEndian)?;
+            return Ok((rsa.private_exponent().into_owned(), rsa.public_key.1.clone()));
+        };
+        let malformed = Cell::new(false);
+        let d = decode_secret(d, ByteOrder::BigEndian, &malformed);
+        let n = decode_secret(&parsed.n, ByteOrder::BigEndian, &malformed);
+        if malformed.get() {
+            return Err(RsaError::InvalidSecretEncoding);
+        }
+        Ok((d, n))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn import_private_key_checked(json: &str) -> Result<RSA, RsaError> {
+        Self::import_private_key_with_byte_order(json, ByteOrder::BigEndian)
+    }
+
+    // `import_private_key_checked` for little-endian components; validation
+    // catches a byte order mismatch
+    #[cfg(feature = "std")]
+    pub fn import_private_key_with_byte_order(
+        json: &str,
+        order: ByteOrder,
+    ) -> Result<RSA, RsaError> {
+        let parsed: RSAPrivateKeyExport = serde_json::from_str(json)?;
+        Self::from_export(&parsed, order)
+    }
+
+    #[cfg(feature = "std")]
+    fn to_export(&self) -> RSAPrivateKeyExport {
+        let n = &self.private_key.1;
+        let encode = |x: &BigUint| Some(BASE64_STANDARD.encode(x.to_bytes_be()));
+        let crt = self.crt.as_ref();
+        RSAPrivateKeyExport {
+            d: encode(&self.private_exponent()),
+            n: BASE64_STANDARD.encode(n.to_bytes_be()),
+            e: encode(&self.public_key.0),
+            p: crt.and_then(|c| encode(&c.p)),
+            q: crt.and_then(|c| encode(&c.q)),
+            dp: crt.and_then(|c| encode(&c.dp)),
+            dq: crt.and_then(|c| encode(&c.dq)),
+            qinv: crt.and_then(|c| encode(&c.qinv)),
+            other_primes: crt.map_or(Vec::new(), |c| {
+                let encode = |x: &BigUint| BASE64_STANDARD.encode(x.to_bytes_be());
+                c.other_primes
+                    .iter()
+                    .map(|other| OtherPrimeExport {
+                        r: encode(&other.r),
+                        d: encode(&other.d),
+                        t: encode(&other.t),
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    // Decodes and validates an export; never hands back an unchecked key. Every
+    // component is decoded before any error is reported, so malformed base64 fails
+    // the same way and after the same work whichever component holds it.
+    #[cfg(feature = "std")]
+    fn from_export(parsed: &RSAPrivateKeyExport, order: ByteOrder) -> Result<RSA, RsaError> {
+        let malformed = Cell::new(false);
+        let component = |encoded: &str| decode_secret(encoded, order, &malformed);
+        let decode = |field: &Option<String>| field.as_deref().map(component);
+
+        // Zero marks a CRT-only key, which needs the full set of CRT parameters below
+        let d = decode(&parsed.d).unwrap_or_default();
+        let n = component(&parsed.n);
+        let e = decode(&parsed.e);
+        let primes = (decode(&parsed.p), decode(&parsed.q));
+        let exponents = (decode(&parsed.dp), decode(&parsed.dq), decode(&parsed.qinv));
+        let other_primes: Vec<_> = parsed
+            .other_primes
+            .iter()
+            .map(|other| OtherPrimeInfo {
+                r: component(&other.r),
+                d: component(&other.d),
+                t: component(&other.t),
+            })
+            .collect();
+        if malformed.get() {
+            return Err(RsaError::InvalidSecretEncoding);
+        }
+        let e = e.ok_or(RsaError::KeyValidationFailed("public exponent is missing"))?;
+        let mut crt = match (primes, exponents) {
+            ((Some(p), Some(q)), (Some(dp), Some(dq), Some(qinv))) => Some(CrtParams {
+                p,
+                q,
+                dp,
+                dq,
+                qinv,
+                other_primes: Vec::new(),
+            }),
+            // Older exports may carry only the primes; recompute the rest once they are
+            // known to be in range and to multiply out to n
+            ((Some(p), Some(q)), _) if !d.is_zero() => {
+                let one = BigUint::one();
+                if p <= one || q <= one {
+                    return Err(RsaError::KeyValidationFailed("prime is out of range"));
+                }
+                let others = other_primes.iter().map(|other| &other.r);
+                if other_primes.iter().any(|other| other.r <= one)
+                    || &p * &q * others.product::<BigUint>() != n
+                {
+                    return Err(RsaError::KeyValidationFailed(
+                        "the product of the primes does not equal n",
+                    ));
+                }
+                Some(
+                    CrtParams::new(p, q, &d)
+                        .ok_or(RsaError::KeyValidationFailed("q is not invertible modulo p"))?,
+                )
+            }
+            _ => None,
+        };
+        match &mut crt {
+            Some(crt) => crt.other_primes = other_primes,
+            None if !other_primes.is_empty() => {
+                return Err(RsaError::KeyValidationFailed("other primes given without p and q"));
+            }
+            None if d.is_zero() => {
+                return Err(RsaError::KeyValidationFailed("private exponent is missing"));
+            }
+            None => {}
+        }
+
+        let rsa = RSA {
+            public_key: (e, n.clone()),
+            private_key: (d, n),
+            crt,
+        };
+        rsa.validate()?;
+        Ok(rsa)
+    }
+
+    // The whole key as one binary blob, e.g. for embedding in an asset:
+    //
+    //     version (1 byte) | e | d | n | prime count (1 byte, 0 without CRT)
+    //     then p | q | dp | dq | qinv | (r | d | t) per further prime
+    //
+    // where each integer is a 4-byte big-endian length and its big-endian bytes. A
+    // CRT-only key keeps d as zero. The blob holds every secret in the clear. A key
+    // with more than five primes fails with `InvalidPrimeCount`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RsaError> {
+        let mut out = vec![KEY_BYTES_VERSION];
+        push_component(&mut out, &self.public_key.0);
+        push_component(&mut out, &self.private_key.0);
+        push_component(&mut out, &self.public_key.1);
+        let Some(crt) = &self.crt else {
+            out.push(0);
+            return Ok(out);
+        };
+        let prime_count = 2 + crt.other_primes.len();
+        if prime_count > KEY_BYTES_MAX_PRIMES {
+            return Err(RsaError::InvalidPrimeCount {
+                got: prime_count,
+                max: KEY_BYTES_MAX_PRIMES,
+            });
+        }
+        out.push(prime_count as u8);
+        for x in [&crt.p, &crt.q, &crt.dp, &crt.dq, &crt.qinv] {
+            push_component(&mut out, x);
+        }
+        for other in &crt.other_primes {
+            for x in [&other.r, &other.d, &other.t] {
+                push_component(&mut out, x);
+            }
+        }
+        Ok(out)
+    }
+
+    // Reads `to_bytes` output back. An unknown version, truncated or trailing data
+    // fail with `InvalidKeyBytes`, and the key is validated before it is returned.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RSA, RsaError> {
+        let (&version, mut rest) = bytes.split_first().ok_or(RsaError::InvalidKeyBytes)?;
+        if version != KEY_BYTES_VERSION {
+            return Err(RsaError::InvalidKeyBytes);
+        }
+        let e = read_component(&mut rest)?;
+        let d = read_component(&mut rest)?;
+        let n = read_component(&mut rest)?;
+        let (&prime_count, mut rest) = rest.split_first().ok_or(RsaError::InvalidKeyBytes)?;
+
+        let crt = match prime_count {
+            0 => None,
+            2.. if prime_count as usize <= KEY_BYTES_MAX_PRIMES => {
+                let mut crt = CrtParams {
+                    p: read_component(&mut rest)?,
+                    q: read_component(&mut rest)?,
+                    dp: read_component(&mut rest)?,
+                    dq: read_component(&mut rest)?,
+                    qinv: read_component(&mut rest)?,
+                    other_primes: Vec::new(),
+                };
+                for _ in 2..prime_count {
+                    crt.other_primes.push(OtherPrimeInfo {
+                        r: read_component(&mut rest)?,
+                        d: read_component(&mut rest)?,
+                        t: read_component(&mut rest)?,
+                    });
+                }
+                Some(crt)
+            }
+            _ => return Err(RsaError::InvalidKeyBytes),
+        };
+        if !rest.is_empty() {
+            return Err(RsaError::InvalidKeyBytes);
+        }
+
+        let rsa = RSA {
+            public_key: (e, n.clone()),
+            private_key: (d, n),
+            crt,
+        };
+        rsa.validate()?;
+        Ok(rsa)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn new(bit_len: usize) -> Result<Self, RsaError> {
+        Self::new_with_exponent(bit_len, BigUint::from(65537u32))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn new_with_exponent(bit_len: usize, e: BigUint) -> Result<Self, RsaError> {
+        let rounds = recommended_rounds(bit_len / 2);
+        Self::generate(bit_len, e, rounds, &mut thread_rng())
+    }
+
+    // Key generation driven by a caller-supplied RNG, e.g. a seeded StdRng in tests
+    pub fn new_with_rng<R: Rng + CryptoRng>(bit_len: usize, rng: &mut R) -> Result<Self, RsaError> {
+        let rounds = recommended_rounds(bit_len / 2);
+        Self::generate(bit_len, BigUint::from(65537u32), rounds, rng)
+    }
+
+    // `mr_rounds` Miller-Rabin rounds are run on each prime candidate
+    #[cfg(feature = "std")]
+    pub fn new_with_params(bit_len: usize, e: BigUint, mr_rounds: usize) -> Result<Self, RsaError> {
+        Self::generate(bit_len, e, mr_rounds, &mut thread_rng())
+    }
+
+    // Reports every candidate tested and prime found; returning `ControlFlow::Break`
+    // from the callback stops generation with `RsaError::Cancelled`
+    #[cfg(feature = "std")]
+    pub fn new_with_progress(
+        bit_len: usize,
+        mut callback: impl FnMut(PrimeGenEvent) -> ControlFlow<()>,
+    ) -> Result<Self, RsaError> {
+        let rounds = recommended_rounds(bit_len / 2);
+        let e = BigUint::from(65537u32);
+        Self::generate_with(bit_len, e, &mut thread_rng(), false, |bits, rng| {
+            generate_prime_with_progress(bits, rounds, rng, &mut callback)
+                .ok_or(RsaError::Cancelled)
+        })
+    }
+
+    // Generates a key whose primes are both safe primes (`(p - 1) / 2` is prime too).
+    //
+    // This is much slower than `RSA::new`, with a large spread between runs: a
+    // 2048-bit key takes anywhere from under a second to tens of seconds. The
+    // search runs on the calling thread.
+    #[cfg(feature = "std")]
+    pub fn new_safe_primes(bit_len: usize) -> Result<Self, RsaError> {
+        RsaKeyGenBuilder::new()
+            .bits(bit_len)
+            .safe_primes(true)
+            .generate()
+    }
+
+    // Generates a key whose modulus is the product of `num_primes` distinct primes
+    // (RFC 8017 multi-prime RSA), which makes private-key operations faster.
+    //
+    // Each prime has about `bit_len / num_primes` bits. To keep every prime well
+    // out of reach of factoring methods that look for small factors, the count is
+    // capped by key size: 2 below 1024 bits, 3 below 4096, 4 below 8192 and 5 above.
+    #[cfg(feature = "std")]
+    pub fn new_multiprime(bit_len: usize, num_primes: usize) -> Result<Self, RsaError> {
+        Self::new_multiprime_with_rng(bit_len, num_primes, &mut thread_rng())
+    }
+
+    pub fn new_multiprime_with_rng<R: Rng + CryptoRng>(
+        bit_len: usize,
+        num_primes: usize,
+        rng: &mut R,
+    ) -> Result<Self, RsaError> {
+        check_key_size(bit_len)?;
+        let max = max_primes(bit_len);
+        if !(2..=max).contains(&num_primes) {
+            return Err(RsaError::InvalidPrimeCount { got: num_primes, max });
+        }
+
+        let prime_bits = bit_len / num_primes;
+        let rounds = recommended_rounds(prime_bits);
+        'attempts: for _ in 0..MAX_KEYGEN_ATTEMPTS {
+            let mut primes: Vec<BigUint> = Vec::with_capacity(num_primes);
+            let mut product = BigUint::one();
+            while primes.len() < num_primes {
+                // The last prime's width is picked so that n lands on exactly bit_len bits
+                let last = primes.len() + 1 == num_primes;
+                let bits = if last {
+                    last_prime_bits(&product, bit_len)
+                } else {
+                    prime_bits
+                };
+
+                // The width only makes the right size likely, so after enough misses
+                // the earlier primes are dropped and the attempt starts over
+                let mut redraws = 0;
+                let r = loop {
+                    let r = generate_prime_with_rounds(bits, rounds, rng);
+                    let apart = primes
+                        .iter()
+                        .all(|other| primes_far_apart(other, &r, bits.min(prime_bits)));
+                    if apart && (!last || (&product * &r).bits() == bit_len as u64) {
+                        break r;
+                    }
+                    redraws += 1;
+                    if redraws == MAX_KEYGEN_ATTEMPTS {
+                        continue 'attempts;
+                    }
+                };
+                product *= &r;
+                primes.push(r);
+            }
+
+            if let Some(rsa) = Self::from_prime_list(primes, &BigUint::from(65537u32)) {
+                debug_assert_eq!(rsa.modulus_bits(), bit_len as u64);
+                return Ok(rsa);
+            }
+        }
+
+        Err(RsaError::NonInvertibleExponent)
+    }
+
+    // Generates `count` key pairs for a key pool, no two of which share a modulus
+    // (see `RSA::shares_modulus_with`) or even a single prime, since
+    // `gcd(n1, n2)` would factor both.
+    //
+    // Independently generated keys collide only if the RNG is broken, e.g. a VM
+    // snapshot restored into several machines; a key that repeats a prime is
+    // redrawn, and generation fails after 16 repeats in a row.
+    #[cfg(feature = "std")]
+    pub fn generate_distinct_keypairs(count: usize, bit_len: usize) -> Result<Vec<Self>, RsaError> {
+        Self::generate_distinct_keypairs_with_rng(count, bit_len, &mut thread_rng())
+    }
+
+    pub fn generate_distinct_keypairs_with_rng<R: Rng + CryptoRng>(
+        count: usize,
+        bit_len: usize,
+        rng: &mut R,
+    ) -> Result<Vec<Self>, RsaError> {
+        check_key_size(bit_len)?;
+        let mut keys = Vec::with_capacity(count);
+        let mut seen_primes: Vec<BigUint> = Vec::with_capacity(2 * count);
+        let mut repeats = 0;
+        while keys.len() < count {
+            let rsa = Self::new_with_rng(bit_len, rng)?;
+            let primes: Vec<&BigUint> = rsa.crt.iter().flat_map(|crt| crt.primes()).collect();
+            let repeated = keys.iter().any(|key: &RSA| key.shares_modulus_with(&rsa))
+                || primes.iter().any(|r| seen_primes.contains(r));
+            if repeated {
+                repeats += 1;
+                if repeats == MAX_KEYGEN_ATTEMPTS {
+                    seen_primes.iter_mut().for_each(wipe_biguint);
+                    return Err(RsaError::KeyValidationFailed(
+                        "the RNG keeps repeating primes",
+                    ));
+                }
+                continue;
+            }
+            repeats = 0;
+            seen_primes.extend(primes.into_iter().cloned());
+            keys.push(rsa);
+        }
+        seen_primes.iter_mut().for_each(wipe_biguint);
+        Ok(keys)
+    }
+
+    pub(crate) fn generate<R: Rng + CryptoRng>(
+        bit_len: usize,
+        e: BigUint,
+        mr_rounds: usize,
+        rng: &mut R,
+    ) -> Result<Self, RsaError> {
+        Self::generate_with(bit_len, e, rng, false, |bits, rng| {
+            Ok(generate_prime_with_rounds(bits, mr_rounds, rng))
+        })
+    }
+
+    // Key generation around a prime source, called as `next_prime(bits, rng)`.
+    // `check_entropy` runs `check_prime_entropy` on every freshly drawn pair.
+    pub(crate) fn generate_with<R, F>(
+        bit_len: usize,
+        e: BigUint,
+        rng: &mut R,
+        check_entropy: bool,
+        mut next_prime: F,
+    ) -> Result<Self, RsaError>
+    where
+        R: Rng + CryptoRng,
+        F: FnMut(usize, &mut R) -> Result<BigUint, RsaError>,
+    {
+        check_key_size(bit_len)?;
+        if e < BigUint::from(3u32) || !e.bit(0) {
+            return Err(RsaError::InvalidExponent);
+        }
+
+        // Both primes have their top two bits set, so n has exactly bit_len bits
+        let (p_bits, q_bits) = prime_sizes(bit_len);
+        // A prime with gcd(e, r - 1) != 1 rules out an inverse whatever it is paired
+        // with, and for e = 3 that is half of all primes, so it is redrawn on its own
+        let mut next_usable_prime = |bits: usize, rng: &mut R| {
+            for _ in 0..MAX_KEYGEN_ATTEMPTS {
+                let r = next_prime(bits, rng)?;
+                if gcd(&e, &(&r - 1u32)).is_one() {
+                    return Ok(r);
+                }
+            }
+            Err(RsaError::NonInvertibleExponent)
+        };
+        for _ in 0..MAX_KEYGEN_ATTEMPTS {
+            let p = next_usable_prime(p_bits, rng)?;
+            let mut q = next_usable_prime(q_bits, rng)?;
+            if check_entropy {
+                check_prime_entropy(&p, &q)?;
+            }
+
+            // Only tiny prime sizes make this loop run more than a handful of times
+            let mut redraws = 0;
+            while !primes_far_apart(&p, &q, q_bits) {
+                redraws += 1;
+                if redraws == MAX_KEYGEN_ATTEMPTS {
+                    return Err(RsaError::KeyValidationFailed("p and q are too close"));
+                }
+                q = next_usable_prime(q_bits, rng)?;
+            }
+
+            // Both p - 1 and q - 1 are coprime to e, so the inverse exists
+            if let Some(rsa) = Self::from_primes(p, q, &e) {
+                debug_assert_eq!(rsa.modulus_bits(), bit_len as u64);
+                return Ok(rsa);
+            }
+        }
+
+        Err(RsaError::NonInvertibleExponent)
+    }
+
+    pub(crate) fn from_primes(p: BigUint, q: BigUint, e: &BigUint) -> Option<Self> {
+        Self::from_prime_list(vec![p, q], e)
+    }
+
+    // Two or more distinct primes; those after the first two become `other_primes`.
+    // d = e^-1 mod lambda(n), the smallest valid d and the one OpenSSL derives
+    // (RFC 8017 allows a d reduced mod phi(n) as well, but tools expect this one).
+    pub(crate) fn from_prime_list(mut primes: Vec<BigUint>, e: &BigUint) -> Option<Self> {
+        let n: BigUint = primes.iter().product();
+        let lambda = primes
+            .iter()
+            .map(|r| r - BigUint::one())
+            .fold(BigUint::one(), |acc, r1| lcm(&acc, &r1));
+        let d = ee_modular_inverse(e, &lambda)?;
+        let others = primes.split_off(2);
+        let q = primes.pop()?;
+        let p = primes.pop()?;
+        let crt = CrtParams::new(p, q, &d).and_then(|crt| crt.with_other_primes(others, &d));
+
+        Some(RSA {
+            public_key: (e.clone(), n.clone()),
+            private_key: (d, n),
+            crt,
+        })
+    }
+
+    // Builds a key straight from its integers, e.g. a published test vector.
+    // No CRT parameters are derived, so private operations use plain modpow.
+    pub fn from_components(e: BigUint, d: BigUint, n: BigUint) -> Self {
+        RSA {
+            public_key: (e, n.clone()),
+            private_key: (d, n),
+            crt: None,
+        }
+    }
+
+    // A key that stores only (p, q, dp, dq, qinv), e.g. for constrained storage:
+    // private operations always go through the CRT and d is never kept. e is still
+    // needed for blinding. The parameters are validated before the key is returned.
+    pub fn from_crt_components(
+        e: BigUint,
+        p: BigUint,
+        q: BigUint,
+        dp: BigUint,
+        dq: BigUint,
+        qinv: BigUint,
+    ) -> Result<RSA, RsaError> {
+        let n = &p * &q;
+        let rsa = RSA {
+            public_key: (e, n.clone()),
+            private_key: (BigUint::zero(), n),
+            crt: Some(CrtParams {
+                p,
+                q,
+                dp,
+                dq,
+                qinv,
+                other_primes: Vec::new(),
+            }),
+        };
+        rsa.validate()?;
+        Ok(rsa)
+    }
+
+    // Wipes the stored d, turning this into a CRT-only key like `from_crt_components`
+    pub fn discard_private_exponent(&mut self) -> Result<(), RsaError> {
+        if self.crt.is_none() {
+            return Err(RsaError::MissingPrimes);
+        }
+        wipe_biguint(&mut self.private_key.0);
+        Ok(())
+    }
+
+    pub fn is_crt_only(&self) -> bool {
+        self.crt.is_some() && self.private_key.0.is_zero()
+    }
+
+    // d itself, or for a CRT-only key d recomputed on demand as e^-1 mod lambda(n), the
+    // form `RSA::new` and OpenSSL produce (an imported key may have held an equivalent
+    // d reduced mod phi(n) instead). Zero if e is not invertible, which `validate` rejects.
+    pub fn private_exponent(&self) -> Cow<'_, BigUint> {
+        let Some(crt) = self.crt.as_ref().filter(|_| self.private_key.0.is_zero()) else {
+            return Cow::Borrowed(&self.private_key.0);
+        };
+        let lambda = crt.lambda().ok();
+        let d = lambda.and_then(|lambda| ee_modular_inverse(&self.public_key.0, &lambda));
+        Cow::Owned(d.unwrap_or_default())
+    }
+
+    // Same n (and primes) under a different public exponent, with d recomputed. A
+    // CRT-only key stays CRT-only.
+    pub fn with_new_exponent(&self, e: BigUint) -> Result<RSA, RsaError> {
+        let crt = self.crt.as_ref().ok_or(RsaError::MissingPrimes)?;
+        if e < BigUint::from(3u32) || !e.bit(0) {
+            return Err(RsaError::InvalidExponent);
+        }
+        let mut rsa = Self::from_prime_list(crt.primes().cloned().collect(), &e)
+            .ok_or(RsaError::NonInvertibleExponent)?;
+        if self.is_crt_only() {
+            rsa.discard_private_exponent()?;
+        }
+        Ok(rsa)
+    }
+
+    // Encrypt/verify-only key
+    pub fn from_public_components(e: BigUint, n: BigUint) -> RSAPublicKey {
+        RSAPublicKey::new(e, n)
+    }
+
+    // Finds e for a bare (d, n), such as `import_private_key` returns, by trying the
+    // exponents in `COMMON_EXPONENTS`: one random x is raised to d, and a candidate
+    // fits if raising the result to it gives x back. Keys with any other e fail
+    // with `KeyValidationFailed`; `recover_public_key_from_primes` has no such limit.
+    #[cfg(feature = "std")]
+    pub fn recover_public_key(private_key: &(BigUint, BigUint)) -> Result<RSAPublicKey, RsaError> {
+        Self::recover_public_key_with_rng(private_key, &mut thread_rng())
+    }
+
+    pub fn recover_public_key_with_rng<R: Rng + CryptoRng>(
+        private_key: &(BigUint, BigUint),
+        rng: &mut R,
+    ) -> Result<RSAPublicKey, RsaError> {
+        let (d, n) = private_key;
+        let two = BigUint::from(2u32);
+        if n <= &two || d.is_zero() {
+            return Err(RsaError::KeyValidationFailed("private key is out of range"));
+        }
+
+        let x = rng.gen_biguint_range(&two, n);
+        let y = private_modpow(&x, d, n);
+        COMMON_EXPONENTS
+            .iter()
+            .map(|&e| BigUint::from(e))
+            .find(|e| y.modpow(e, n) == x)
+            .map(|e| RSAPublicKey::new(e, n.clone()))
+            .ok_or(RsaError::KeyValidationFailed("no common public exponent matches d"))
+    }
+
+    // e = d^-1 mod lcm(p - 1, q - 1), which gives back the original e whether d was
+    // reduced modulo phi(n) or modulo lcm(p - 1, q - 1)
+    pub fn recover_public_key_from_primes(
+        d: &BigUint,
+        p: &BigUint,
+        q: &BigUint,
+    ) -> Result<RSAPublicKey, RsaError> {
+        let one = BigUint::one();
+        if p <= &one || q <= &one {
+            return Err(RsaError::KeyValidationFailed("prime is out of range"));
+        }
+        let lambda = lcm(&(p - &one), &(q - &one));
+        let e = ee_modular_inverse(&(d % &lambda), &lambda)
+            .ok_or(RsaError::NonInvertibleExponent)?;
+        Ok(RSAPublicKey::new(e, p * q))
+    }
+
+    // phi(n) and lambda(n) from the primes, for keys that keep them (see `CrtParams`)
+    pub fn phi(&self) -> Result<BigUint, RsaError> {
+        self.crt.as_ref().ok_or(RsaError::MissingPrimes)?.phi()
+    }
+
+    pub fn lambda(&self) -> Result<BigUint, RsaError> {
+        self.crt.as_ref().ok_or(RsaError::MissingPrimes)?.lambda()
+    }
+
+    pub fn public(&self) -> RSAPublicKey {
+        let (e, n) = &self.public_key;
+        RSAPublicKey::new(e.clone(), n.clone())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn export_public_key(&self) -> String {
+        serde_json::to_string(&RSAPublicKeyExport::from(&self.public_key)).unwrap()
+    }
+
+    pub fn modulus_bits(&self) -> u64 {
+        self.public_key.1.bits()
+    }
+
+    // Whether both keys use the same modulus `n`.
+    //
+    // Never reuse a modulus across key pairs. If one message is encrypted under
+    // `(e1, n)` and `(e2, n)` with coprime exponents, anyone can recover it from
+    // the two ciphertexts without a private key (Simmons' common-modulus attack),
+    // and the holder of either private key can factor `n` and derive the other.
+    pub fn shares_modulus_with(&self, other: &RSA) -> bool {
+        self.public_key.1 == other.public_key.1
+    }
+
+    // Close to `modulus_bits` for a generated key; far below it is a Wiener risk (see `audit`)
+    pub fn private_exponent_bits(&self) -> u64 {
+        self.private_exponent().bits()
+    }
+
+    pub fn max_message_len(&self) -> usize {
+        self.public().max_message_len()
+    }
+
+    // Both equal the modulus size in bytes, for sizing protocol fields up front
+    pub fn ciphertext_len(&self) -> usize {
+        modulus_byte_len(&self.public_key.1)
+    }
+
+    pub fn signature_len(&self) -> usize {
+        modulus_byte_len(&self.public_key.1)
+    }
+
+    pub fn public_key_fingerprint(&self) -> String {
+        self.public().public_key_fingerprint()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn encrypt(&self, message: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt(message)
+    }
+
+    pub fn encrypt_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_with_rng(message, rng)
+    }
+
+    // Unlike most encrypt variants this skips `public()`, so nothing is cloned per call
+    #[cfg(feature = "std")]
+    pub fn encrypt_into(&self, message: &[u8], out: &mut Vec<u8>) -> Result<(), RsaError> {
+        self.encrypt_into_with_rng(message, out, &mut thread_rng())
+    }
+
+    pub fn encrypt_into_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        out: &mut Vec<u8>,
+        rng: &mut R,
+    ) -> Result<(), RsaError> {
+        let (e, n) = &self.public_key;
+        pkcs1_encrypt_into(e, n, message, out, rng)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn encrypt_hex(&self, hex_message: &str) -> Result<String, RsaError> {
+        self.public().encrypt_hex(hex_message)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn encrypt_batch(&self, messages: &[&[u8]]) -> Result<Vec<Vec<u8>>, RsaError> {
+        self.public().encrypt_batch(messages)
+    }
+
+    pub fn encrypt_batch_with_rng<R: Rng + CryptoRng>(
+        &self,
+        messages: &[&[u8]],
+        rng: &mut R,
+    ) -> Result<Vec<Vec<u8>>, RsaError> {
+        self.public().encrypt_batch_with_rng(messages, rng)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn encrypt_chunked(&self, message: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_chunked(message)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn encrypt_oaep(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        label: &[u8],
+    ) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_oaep(message, hash, label)
+    }
 
-pub struct RSA {
-    pub public_key: (BigUint, BigUint),  // (e, n)
-    pub private_key: (BigUint, BigUint),     // (d, n)
-}
+    pub fn encrypt_oaep_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_oaep_with_rng(message, hash, label, rng)
+    }
 
-impl RSA {
-    pub fn export_public_key(&self) -> String {
-        let (e, n) = &self.public_key;
-        let export = RSAPublicKeyExport {
-            e: BASE64_STANDARD.encode(e.to_bytes_be()),
-            n: BASE64_STANDARD.encode(n.to_bytes_be()),
-        };
-        serde_json::to_string(&export).unwrap()
+    pub fn verify(&self, message: &[u8], signature: &[u8], hash: HashAlgorithm) -> bool {
+        self.public().verify(message, signature, hash)
     }
 
-    pub fn export_private_key(&self) -> String {
-        let (d, n) = &self.private_key;
-        let export = RSAPrivateKeyExport {
-            d: BASE64_STANDARD.encode(d.to_bytes_be()),
-            n: BASE64_STANDARD.encode(n.to_bytes_be()),
-        };
-        serde_json::to_string(&export).unwrap()
+    pub fn public_op(&self, data: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.public().public_op(data)
     }
 
-    pub fn import_public_key(json: &str) -> (BigUint, BigUint) {
-        let parsed: RSAPublicKeyExport = serde_json::from_str(json).unwrap();
-        let e = BigUint::from_bytes_be(&BASE64_STANDARD.decode(&parsed.e).unwrap());
-        let n = BigUint::from_bytes_be(&BASE64_STANDARD.decode(&parsed.n).unwrap());
-        (e, n)
+    // Test and debugging use only; see `RSAPublicKey::encrypt_with_padding_bytes`.
+    pub fn encrypt_with_padding_bytes(
+        &self,
+        message: &[u8],
+        padding: &[u8],
+    ) -> Result<Vec<u8>, RsaError> {
+        self.public().encrypt_with_padding_bytes(message, padding)
     }
 
-    pub fn import_private_key(json: &str) -> (BigUint, BigUint) {
-        let parsed: RSAPrivateKeyExport = serde_json::from_str(json).unwrap();
-        let d = BigUint::from_bytes_be(&BASE64_STANDARD.decode(&parsed.d).unwrap());
-        let n = BigUint::from_bytes_be(&BASE64_STANDARD.decode(&parsed.n).unwrap());
-        (d, n)
+    // Textbook RSA: `m^e mod n` with no padding. See `RSAPublicKey::encrypt_raw`.
+    pub fn encrypt_raw(&self, m: &BigUint) -> BigUint {
+        self.public().encrypt_raw(m)
     }
 
-    pub fn new(bit_len: usize) -> Self {
-        let p = generate_prime(bit_len / 2);
-        let q = generate_prime(bit_len / 2);
+    // Textbook RSA: `c^d mod n` with no padding or blinding.
+    //
+    // **Not for real use.** Besides the problems of `RSAPublicKey::encrypt_raw`,
+    // the running time depends on `c`, and applying the private key to arbitrary
+    // inputs makes the key holder a decryption and signing oracle. Meant for
+    // conformance test vectors only.
+    pub fn decrypt_raw(&self, c: &BigUint) -> BigUint {
+        self.private_op_unblinded(c)
+    }
 
-        let n = &p * &q;
-        let phi = (&p - BigUint::one()) * (&q - BigUint::one());
+    // Salt lengths equal to `hash.output_len()` are the usual choice
+    #[cfg(feature = "std")]
+    pub fn sign_pss(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        salt_len: usize,
+    ) -> Result<Vec<u8>, RsaError> {
+        self.sign_pss_with_rng(message, hash, salt_len, &mut thread_rng())
+    }
 
-        let e = BigUint::from(65537u32);
-        let d = ee_modular_inverse(&e, &phi).expect("Failed to compute modular inverse!");
+    // `rng` supplies both the salt and the blinding factor
+    pub fn sign_pss_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        salt_len: usize,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let n = &self.private_key.1;
+        let modulus_bytes = modulus_byte_len(n);
+        let em_bits = (n.bits() - 1) as usize;
+        check_salt_len(em_bits, hash, salt_len)?;
 
-        RSA {
-            public_key: (e, n.clone()),
-            private_key: (d, n),
+        let mut salt = vec![0u8; salt_len];
+        rng.fill(&mut salt[..]);
+        let encoded = pss_encode(message, em_bits, hash, &salt)?;
+
+        let s = self.private_op(&BigUint::from_bytes_be(&encoded), rng);
+        Ok(left_pad(&s.to_bytes_be(), modulus_bytes))
+    }
+
+    pub fn verify_pss(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        hash: HashAlgorithm,
+        salt_len: usize,
+    ) -> bool {
+        self.public().verify_pss(message, signature, hash, salt_len)
+    }
+
+    // Constant-time in the private exponent; the public tuples are compared normally
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        if self.public_key != other.public_key || self.private_key.1 != other.private_key.1 {
+            return false;
         }
+        let a = self.private_exponent().to_bytes_be();
+        let b = other.private_exponent().to_bytes_be();
+        // Pad both to the modulus width so the comparison length does not depend on d
+        let len = modulus_byte_len(&self.public_key.1).max(a.len()).max(b.len());
+        ct_eq(&left_pad(&a, len), &left_pad(&b, len))
     }
 
-    pub fn encrypt(&self, message: &[u8]) -> Vec<u8> {
-        let modulus_bytes = ((self.public_key.1.bits() + 7) / 8) as usize;
-        let padded = pkcs1_pad(message, modulus_bytes);
-        let m = BigUint::from_bytes_be(&padded);
-        let (e, n) = &self.public_key;
+    pub fn crt_params(&self) -> Option<&CrtParams> {
+        self.crt.as_ref()
+    }
+
+    // None for keys built from (d, n) alone, e.g. via `from_components`. Multi-prime
+    // keys keep their remaining primes in `crt_params().other_primes`.
+    pub fn primes(&self) -> Option<(&BigUint, &BigUint)> {
+        self.crt.as_ref().map(|crt| (&crt.p, &crt.q))
+    }
+
+    // Every decrypt variant returns an error rather than panicking on any ciphertext,
+    // including empty input and input far longer than the modulus, which is rejected
+    // before anything is allocated for it. This holds for keys that pass `validate`;
+    // see fuzz/ for the target that exercises it.
+    #[cfg(feature = "std")]
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, RsaError> {
+        self.decrypt_with_rng(ciphertext, &mut thread_rng())
+    }
+
+    // `rng` supplies the blinding factor
+    pub fn decrypt_with_rng<R: Rng + CryptoRng>(
+        &self,
+        ciphertext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let mut out = Vec::new();
+        self.decrypt_into_with_rng(ciphertext, &mut out, rng)?;
+        Ok(out)
+    }
+
+    // Reuses `out` for the padded block and then the plaintext. On success it holds
+    // exactly the message; on any error it is wiped and left empty.
+    #[cfg(feature = "std")]
+    pub fn decrypt_into(&self, ciphertext: &[u8], out: &mut Vec<u8>) -> Result<(), RsaError> {
+        self.decrypt_into_with_rng(ciphertext, out, &mut thread_rng())
+    }
 
-        let c = m.modpow(e, n);
-        let mut ciphertext = c.to_bytes_be();
-        while ciphertext.len() < modulus_bytes {
-            ciphertext.insert(0, 0);
+    pub fn decrypt_into_with_rng<R: Rng + CryptoRng>(
+        &self,
+        ciphertext: &[u8],
+        out: &mut Vec<u8>,
+        rng: &mut R,
+    ) -> Result<(), RsaError> {
+        let start = self
+            .apply_private_into(ciphertext, |c| self.private_op(c, rng), out)
+            .and_then(|()| pkcs1_unpad_start(out).ok_or(RsaError::InvalidPadding));
+        match start {
+            Ok(start) => {
+                out.drain(..start);
+                Ok(())
+            }
+            Err(err) => {
+                wipe_bytes(out);
+                out.clear();
+                Err(err)
+            }
         }
-        ciphertext
     }
 
-    pub fn decrypt(&self, ciphertext: &[u8]) -> Vec<u8> {
-        let (d, n) = &self.private_key;
-        let c = BigUint::from_bytes_be(ciphertext);
-        let m = c.modpow(d, n);
+    #[cfg(feature = "std")]
+    pub fn decrypt_hex(&self, hex_ciphertext: &str) -> Result<String, RsaError> {
+        Ok(encode_hex(&self.decrypt(&decode_hex(hex_ciphertext)?)?))
+    }
 
-        let mut padded = m.to_bytes_be();
-        let modulus_bytes = ((n.bits() + 7) / 8) as usize;
-        while padded.len() < modulus_bytes {
-            padded.insert(0, 0);
+    // Same as `decrypt` without base blinding; leaks timing, meant for benchmarks only
+    pub fn decrypt_unblinded(&self, ciphertext: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let padded = self.decrypt_block_unblinded(ciphertext)?;
+        pkcs1_unpad(&padded).ok_or(RsaError::InvalidPadding)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn decrypt_chunked(&self, ciphertext: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let n = &self.private_key.1;
+        let modulus_bytes = modulus_byte_len(n);
+        // A zero modulus (only possible via `from_components`) would make `chunks` panic
+        if modulus_bytes == 0 || !ciphertext.len().is_multiple_of(modulus_bytes) {
+            return Err(RsaError::InvalidCiphertextLength);
         }
 
-        pkcs1_unpad(&padded).expect("Invalid padding after decryption")
+        let mut message = Vec::new();
+        for block in ciphertext.chunks(modulus_bytes) {
+            message.extend(self.decrypt(block)?);
+        }
+        Ok(message)
     }
-}
 
-// Miller-Rabin primality test
-fn is_prime(n: &BigUint, k: usize) -> bool {
-    if n <= &BigUint::one() {
-        return false;
+    // `label` must be the one the ciphertext was encrypted with (RFC 8017 section 7.1),
+    // e.g. a per-context tag binding it to its use; otherwise `OaepLabelMismatch`
+    #[cfg(feature = "std")]
+    pub fn decrypt_oaep(
+        &self,
+        ciphertext: &[u8],
+        hash: HashAlgorithm,
+        label: &[u8],
+    ) -> Result<Vec<u8>, RsaError> {
+        self.decrypt_oaep_with_rng(ciphertext, hash, label, &mut thread_rng())
     }
-    if n <= &BigUint::from(3u32) {
-        return true;
+
+    // `rng` supplies the blinding factor
+    pub fn decrypt_oaep_with_rng<R: Rng + CryptoRng>(
+        &self,
+        ciphertext: &[u8],
+        hash: HashAlgorithm,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let mut padded = self.decrypt_block(ciphertext, rng)?;
+        let message = oaep_decode(&padded, hash, label);
+        wipe_bytes(&mut padded);
+        message
+    }
+
+    // For migrating from PKCS#1 v1.5 to OAEP (with `hash` and an empty label): accepts
+    // either and reports which one it found. OAEP is tried first, because about one
+    // OAEP block in 256 also begins 0x00 0x02 and often parses as PKCS#1, while a
+    // PKCS#1 block passes the OAEP label check with negligible probability.
+    //
+    // Detection is a heuristic, not a guarantee, and it turns every ciphertext into a
+    // PKCS#1 padding oracle just as `decrypt` does. Pick the scheme explicitly
+    // whenever the sender is known.
+    #[cfg(feature = "std")]
+    pub fn decrypt_auto(
+        &self,
+        ciphertext: &[u8],
+        hash: HashAlgorithm,
+    ) -> Result<(Vec<u8>, PaddingScheme), RsaError> {
+        let mut padded = self.decrypt_block(ciphertext, &mut thread_rng())?;
+        let found = match oaep_unpad(&padded, hash, b"") {
+            Some(message) => Some((message, PaddingScheme::Oaep(hash))),
+            None => pkcs1_unpad_start(&padded)
+                .map(|start| (padded[start..].to_vec(), PaddingScheme::Pkcs1v15)),
+        };
+        wipe_bytes(&mut padded);
+        found.ok_or(RsaError::InvalidPadding)
+    }
+
+    // Fails with `KeyTooSmall` if the DigestInfo for `hash` does not fit the modulus
+    #[cfg(feature = "std")]
+    pub fn sign(&self, message: &[u8], hash: HashAlgorithm) -> Result<Vec<u8>, RsaError> {
+        self.sign_with_rng(message, hash, &mut thread_rng())
+    }
+
+    // `rng` supplies the blinding factor; the signature itself is deterministic
+    pub fn sign_with_rng<R: Rng + CryptoRng>(
+        &self,
+        message: &[u8],
+        hash: HashAlgorithm,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        self.sign_digest_with_rng(&hash.digest(message), hash, rng)
     }
 
-    let mut d = n - BigUint::one();
-    let mut s = 0;
-    while &d % 2u32 == BigUint::zero() {
-        d /= 2u32;
-        s += 1;
+    // `digest` must be `hash`'s output for the message
+    pub(crate) fn sign_digest_with_rng<R: Rng + CryptoRng>(
+        &self,
+        digest: &[u8],
+        hash: HashAlgorithm,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let n = &self.private_key.1;
+        let modulus_bytes = modulus_byte_len(n);
+        let encoded = emsa_pkcs1_v15_encode(digest, modulus_bytes, hash)?;
+
+        let s = self.private_op(&BigUint::from_bytes_be(&encoded), rng);
+        Ok(left_pad(&s.to_bytes_be(), modulus_bytes))
     }
 
-    let mut rng = thread_rng();
-    for _ in 0..k {
-        let a = rng.gen_biguint_range(&BigUint::from(2u32), &(n - 2u32));
-        let mut x = a.modpow(&d, n);
-        if x == BigUint::one() || x == n - 1u32 {
-            continue;
+    // Consistency checks on the key material, e.g. after `from_components`
+    pub fn validate(&self) -> Result<(), RsaError> {
+        let (e, n) = &self.public_key;
+        let one = BigUint::one();
+        let fail = RsaError::KeyValidationFailed;
+
+        // Ranges first: a CRT-only key derives d from the primes, which must be > 1
+        if &self.private_key.1 != n {
+            return Err(fail("public and private moduli differ"));
+        }
+        if !n.bit(0) || n.bits() < MIN_KEY_BITS as u64 {
+            return Err(fail("modulus is too small or even"));
+        }
+        if e <= &one || e >= n {
+            return Err(fail("public exponent is out of range"));
+        }
+        if let Some(crt) = &self.crt {
+            if crt.primes().any(|r| r <= &one) {
+                return Err(fail("prime is out of range"));
+            }
+            if &crt.primes().product::<BigUint>() != n {
+                return Err(fail("the product of the primes does not equal n"));
+            }
         }
+        let d = &*self.private_exponent();
+        if d < &one || d >= n {
+            return Err(fail("private exponent is out of range"));
+        }
+
+        if let Some(crt) = &self.crt {
+            // e*d = 1 mod (p-1) and mod (q-1) is exactly e*d = 1 mod lcm(p-1, q-1)
+            let p1 = &crt.p - &one;
+            let q1 = &crt.q - &one;
+            let ed = e * d;
+            if &ed % &p1 != one || &ed % &q1 != one {
+                return Err(fail("e * d is not 1 modulo phi"));
+            }
+            if crt.dp != d % &p1 || crt.dq != d % &q1 || (&crt.qinv * &crt.q) % &crt.p != one {
+                return Err(fail("CRT parameters are inconsistent"));
+            }
 
-        let mut is_composite = true;
-        for _ in 0..s - 1 {
-            x = x.modpow(&BigUint::from(2u32), n);
-            if x == n - 1u32 {
-                is_composite = false;
-                break;
+            let mut product = &crt.p * &crt.q;
+            for other in &crt.other_primes {
+                let r1 = &other.r - &one;
+                if &ed % &r1 != one {
+                    return Err(fail("e * d is not 1 modulo phi"));
+                }
+                if other.d != d % &r1 || (&other.t * &product) % &other.r != one {
+                    return Err(fail("CRT parameters are inconsistent"));
+                }
+                product *= &other.r;
             }
         }
 
-        if is_composite {
+        Ok(())
+    }
+
+    // Functional check that the key pair works: a fresh random value has to survive a
+    // raw encrypt/decrypt round trip. Unlike `validate`, this needs neither p nor q.
+    #[cfg(feature = "std")]
+    pub fn is_consistent(&self) -> bool {
+        self.is_consistent_with_rng(&mut thread_rng())
+    }
+
+    pub fn is_consistent_with_rng<R: Rng + CryptoRng>(&self, rng: &mut R) -> bool {
+        let n = &self.public_key.1;
+        let two = BigUint::from(2u32);
+        if &self.private_key.1 != n || n <= &two {
             return false;
         }
+        let m = rng.gen_biguint_range(&two, n);
+        self.decrypt_raw(&self.encrypt_raw(&m)) == m
     }
 
-    true
-}
+    // Applies the private key and returns the recovered block, left-padded to the modulus size
+    fn decrypt_block<R: Rng + CryptoRng>(
+        &self,
+        ciphertext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, RsaError> {
+        let mut out = Vec::new();
+        self.apply_private_into(ciphertext, |c| self.private_op(c, rng), &mut out)?;
+        Ok(out)
+    }
+
+    fn decrypt_block_unblinded(&self, ciphertext: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let mut out = Vec::new();
+        self.apply_private_into(ciphertext, |c| self.private_op_unblinded(c), &mut out)?;
+        Ok(out)
+    }
+
+    fn apply_private_into(
+        &self,
+        ciphertext: &[u8],
+        op: impl FnOnce(&BigUint) -> BigUint,
+        out: &mut Vec<u8>,
+    ) -> Result<(), RsaError> {
+        let n = &self.private_key.1;
+        let modulus_bytes = modulus_byte_len(n);
+        if ciphertext.len() > modulus_bytes {
+            return Err(RsaError::InvalidCiphertextLength);
+        }
+
+        // Shorter inputs (leading zeros stripped by the sender) are fine, but a value
+        // at or above n would be silently reduced and decrypt to garbage
+        let c = BigUint::from_bytes_be(ciphertext);
+        if &c >= n {
+            return Err(RsaError::CiphertextOutOfRange);
+        }
+        write_padded_bytes(&op(&c), modulus_bytes, out);
+        Ok(())
+    }
+
+    // Base blinding: (x * r^e)^d * r^-1 = x^d mod n, but the exponentiation no
+    // longer operates on a value the caller chose
+    pub(crate) fn private_op<R: Rng + CryptoRng>(&self, x: &BigUint, rng: &mut R) -> BigUint {
+        let (e, n) = &self.public_key;
+        let (r, r_inv) = loop {
+            let r = rng.gen_biguint_below(n);
+            if let Some(r_inv) = ee_modular_inverse(&r, n) {
+                break (r, r_inv);
+            }
+        };
+
+        let blinded = (x * r.modpow(e, n)) % n;
+        (self.private_op_unblinded(&blinded) * r_inv) % n
+    }
 
-fn generate_prime(bit_length: usize) -> BigUint {
-    let mut rng = thread_rng();
-    loop {
-        let mut num = rng.gen_biguint(bit_length as u64);
-        num.set_bit((bit_length as u64) - 1, true); // Garante bit mais alto
-        num.set_bit(0, true);              // Garante que é ímpar
+    // x^d mod n, using the CRT shortcut when the primes are known
+    fn private_op_unblinded(&self, x: &BigUint) -> BigUint {
+        let (d, n) = &self.private_key;
+        let Some(crt) = &self.crt else {
+            return private_modpow(x, d, n);
+        };
+
+        let m1 = private_modpow(x, &crt.dp, &crt.p);
+        let m2 = private_modpow(x, &crt.dq, &crt.q);
+
+        // Garner: h = qinv * (m1 - m2) mod p, m = m2 + h * q
+        let diff = (&m1 + &crt.p - (&m2 % &crt.p)) % &crt.p;
+        let h = (&crt.qinv * diff) % &crt.p;
+        let mut m = m2 + h * &crt.q;
 
-        if is_prime(&num, 5) {
-            return num;
+        // Each further prime extends the result from mod r_1..r_(i-1) to mod r_1..r_i
+        let mut product = &crt.p * &crt.q;
+        for other in &crt.other_primes {
+            let mi = private_modpow(x, &other.d, &other.r);
+            let diff = (&mi + &other.r - (&m % &other.r)) % &other.r;
+            let h = (&other.t * diff) % &other.r;
+            m += h * &product;
+            product *= &other.r;
         }
+        m
     }
 }
 
-fn ee_modular_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
-    let (mut t, mut new_t) = (BigInt::zero(), BigInt::one());
-    let (mut r, mut new_r) = (m.to_bigint().unwrap(), a.to_bigint().unwrap());
+// Exponentiation with a secret exponent. Constant-time unless the `fast-insecure`
+// feature swaps in the variable-time `BigUint::modpow` for benchmarking.
+fn private_modpow(x: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+    #[cfg(feature = "fast-insecure")]
+    return x.modpow(exponent, modulus);
+    #[cfg(not(feature = "fast-insecure"))]
+    return modpow_ct(x, exponent, modulus);
+}
+
+// Bytes needed to hold any value below n, and so the length of every ciphertext and
+// signature under it: a 2047-bit modulus still takes 256 bytes
+pub fn modulus_byte_len(n: &BigUint) -> usize {
+    n.bits().div_ceil(8) as usize
+}
+
+// An odd bit_len gives p the extra bit
+pub(crate) fn prime_sizes(bit_len: usize) -> (usize, usize) {
+    (bit_len - bit_len / 2, bit_len / 2)
+}
+
+// Upper bound on the number of primes for a modulus size, keeping each prime
+// above roughly 340 bits (the limits OpenSSL applies)
+fn max_primes(bit_len: usize) -> usize {
+    match bit_len {
+        0..1024 => 2,
+        1024..4096 => 3,
+        4096..8192 => 4,
+        _ => 5,
+    }
+}
+
+// Width of the final prime given the product of the others. That product is
+// 2^b * f with f in [1/2, 1) and the prime contributes a factor in [3/4, 1), so
+// when f < 4/7 a prime one bit wider is the likelier fit.
+fn last_prime_bits(product: &BigUint, bit_len: usize) -> usize {
+    let b = product.bits() as usize;
+    let short = product * 7u32 < (BigUint::one() << b) * 4u32;
+    bit_len - b + usize::from(short)
+}
+
+pub(crate) fn check_key_size(bit_len: usize) -> Result<(), RsaError> {
+    if bit_len < MIN_KEY_BITS {
+        return Err(RsaError::KeyTooSmall {
+            bits: bit_len,
+            min: MIN_KEY_BITS,
+        });
+    }
+    Ok(())
+}
+
+// |p - q| must exceed 2^(prime_bits - 100) (FIPS 186-4, B.3.1), otherwise n can be
+// factored with Fermat's method. Below 100-bit primes this only rules out p == q.
+pub(crate) fn primes_far_apart(p: &BigUint, q: &BigUint, prime_bits: usize) -> bool {
+    let distance = if p > q { p - q } else { q - p };
+    distance > BigUint::one() << prime_bits.saturating_sub(100)
+}
+
+// Catches an RNG that repeats itself rather than a subtly biased one. Independent
+// primes share their top 64 bits, or a common factor of p - 1 and q - 1 above 2^64,
+// with probability around 2^-60; a badly seeded generator does so every time.
+pub(crate) fn check_prime_entropy(p: &BigUint, q: &BigUint) -> Result<(), RsaError> {
+    let top = |x: &BigUint| x >> x.bits().saturating_sub(64);
+    if top(p) == top(q) {
+        return Err(RsaError::WeakRandomness);
+    }
+    let one = BigUint::one();
+    if gcd(&(p - &one), &(q - &one)).bits() > 64 {
+        return Err(RsaError::WeakRandomness);
+    }
+    Ok(())
+}
 
-    while new_r != BigInt::zero() {
+// Greatest common divisor by Euclid's algorithm; gcd(0, 0) is 0
+pub fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while !b.is_zero() {
+        let r = &a % &b;
+        a = core::mem::replace(&mut b, r);
+    }
+    a
+}
+
+fn lcm(a: &BigUint, b: &BigUint) -> BigUint {
+    if a.is_zero() || b.is_zero() {
+        return BigUint::zero();
+    }
+    a / gcd(a, b) * b
+}
+
+// Returns (g, x, y) with a*x + b*y = g, where g = gcd(|a|, |b|) is never negative.
+// Not constant-time: the number of steps depends on the inputs.
+pub fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut r, mut new_r) = (a.clone(), b.clone());
+    let (mut x, mut new_x) = (BigInt::one(), BigInt::zero());
+    let (mut y, mut new_y) = (BigInt::zero(), BigInt::one());
+
+    while !new_r.is_zero() {
         let quotient = &r / &new_r;
+        let next_r = &r - &quotient * &new_r;
+        let next_x = &x - &quotient * &new_x;
+        let next_y = &y - &quotient * &new_y;
+        r = core::mem::replace(&mut new_r, next_r);
+        x = core::mem::replace(&mut new_x, next_x);
+        y = core::mem::replace(&mut new_y, next_y);
+    }
 
-        // Corrigido: calcula antes, depois atualiza
-        let temp_t = &t - &quotient * &new_t;
-        let temp_r = &r - &quotient * &new_r;
+    if r < BigInt::zero() {
+        (-r, -x, -y)
+    } else {
+        (r, x, y)
+    }
+}
+
+// A 4-byte big-endian length, then the value's big-endian bytes (see `RSA::to_bytes`)
+fn push_component(out: &mut Vec<u8>, x: &BigUint) {
+    let mut bytes = x.to_bytes_be();
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(&bytes);
+    wipe_bytes(&mut bytes);
+}
 
-        t = std::mem::replace(&mut new_t, temp_t);
-        r = std::mem::replace(&mut new_r, temp_r);
+fn read_component(rest: &mut &[u8]) -> Result<BigUint, RsaError> {
+    let (len, tail) = rest.split_first_chunk::<4>().ok_or(RsaError::InvalidKeyBytes)?;
+    let len = u32::from_be_bytes(*len) as usize;
+    if tail.len() < len {
+        return Err(RsaError::InvalidKeyBytes);
     }
+    let (value, tail) = tail.split_at(len);
+    *rest = tail;
+    Ok(BigUint::from_bytes_be(value))
+}
+
+// `decode_component` for private keys: constant-time base64, and a failure is
+// recorded in `malformed` rather than returned, so the caller can finish decoding
+// the rest of the key first
+#[cfg(feature = "std")]
+fn decode_secret(encoded: &str, order: ByteOrder, malformed: &Cell<bool>) -> BigUint {
+    let Some(mut bytes) = ct_base64_decode(encoded.as_bytes()) else {
+        malformed.set(true);
+        return BigUint::zero();
+    };
+    let value = order.to_biguint(&bytes);
+    wipe_bytes(&mut bytes);
+    value
+}
+
+#[cfg(feature = "std")]
+fn decode_component(encoded: &str, order: ByteOrder) -> Result<BigUint, RsaError> {
+    let bytes = BASE64_STANDARD.decode(encoded)?;
+    Ok(order.to_biguint(&bytes))
+}
 
-    if r != BigInt::one() {
+fn ee_modular_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    if m.is_zero() {
+        return None;
+    }
+    let m = m.to_bigint().unwrap();
+    let (g, x, _) = extended_gcd(&a.to_bigint().unwrap(), &m);
+    if !g.is_one() {
         return None;
     }
 
-    if t < BigInt::zero() {
-        t += m.to_bigint().unwrap();
+    // |x| <= m, so one addition and a reduction bring it into 0..m
+    let x = if x < BigInt::zero() { x + &m } else { x };
+    (x % m).to_biguint()
+}
+
+// PKCS#1 v1.5 encryption with a bare (e, n) tuple
+#[cfg(feature = "std")]
+pub fn rsa_encrypt(message: &[u8], public_key: &(BigUint, BigUint)) -> Result<Vec<u8>, RsaError> {
+    let (e, n) = public_key;
+    RSAPublicKey::new(e.clone(), n.clone()).encrypt(message)
+}
+
+// Inverse of `rsa_encrypt` with a bare (d, n) tuple. Without e there is no base
+// blinding, so the timing of this call depends on the ciphertext; prefer `RSA::decrypt`.
+pub fn rsa_decrypt(
+    ciphertext: &[u8],
+    private_key: &(BigUint, BigUint),
+) -> Result<Vec<u8>, RsaError> {
+    let (d, n) = private_key;
+    let modulus_bytes = modulus_byte_len(n);
+    if ciphertext.len() > modulus_bytes {
+        return Err(RsaError::InvalidCiphertextLength);
+    }
+
+    let c = BigUint::from_bytes_be(ciphertext);
+    if &c >= n {
+        return Err(RsaError::CiphertextOutOfRange);
     }
+    let m = private_modpow(&c, d, n);
+    pkcs1_unpad(&to_padded_bytes(&m, modulus_bytes)).ok_or(RsaError::InvalidPadding)
+}
 
-    Some(t.to_biguint().unwrap())
+#[cfg(feature = "std")]
+pub fn pkcs1_pad(message: &[u8], modulus_bytes: usize) -> Result<Vec<u8>, RsaError> {
+    pkcs1_pad_with_rng(message, modulus_bytes, &mut thread_rng())
 }
 
+pub fn pkcs1_pad_with_rng<R: Rng + CryptoRng>(
+    message: &[u8],
+    modulus_bytes: usize,
+    rng: &mut R,
+) -> Result<Vec<u8>, RsaError> {
+    let mut padded = Vec::new();
+    pkcs1_pad_into(message, modulus_bytes, &mut padded, rng)?;
+    Ok(padded)
+}
 
-pub fn pkcs1_pad(message: &[u8], modulus_bytes: usize) -> Vec<u8> {
-    let mut rng = thread_rng();
-    let max_msg_len = modulus_bytes - 11;
-    assert!(message.len() <= max_msg_len, "Message too long for RSA modulus");
+fn pkcs1_pad_into<R: Rng + CryptoRng>(
+    message: &[u8],
+    modulus_bytes: usize,
+    padded: &mut Vec<u8>,
+    rng: &mut R,
+) -> Result<(), RsaError> {
+    let max_msg_len = modulus_bytes.saturating_sub(11);
+    if modulus_bytes < 11 || message.len() > max_msg_len {
+        return Err(RsaError::MessageTooLong {
+            got: message.len(),
+            max: max_msg_len,
+        });
+    }
 
-    let mut padded = vec![0x00, 0x02];
+    padded.clear();
+    padded.extend_from_slice(&[0x00, 0x02]);
     while padded.len() < modulus_bytes - message.len() - 1 {
         let mut byte = rng.r#gen::<u8>();
         while byte == 0 {
@@ -196,22 +2057,596 @@ pub fn pkcs1_pad(message: &[u8], modulus_bytes: usize) -> Vec<u8> {
 
     padded.push(0x00);
     padded.extend_from_slice(message);
-    padded
+    Ok(())
+}
+
+// PKCS#1 v1.5 encryption under (e, n); `out` is the padding buffer first and then
+// receives the ciphertext in place
+fn pkcs1_encrypt_into<R: Rng + CryptoRng>(
+    e: &BigUint,
+    n: &BigUint,
+    message: &[u8],
+    out: &mut Vec<u8>,
+    rng: &mut R,
+) -> Result<(), RsaError> {
+    let modulus_bytes = modulus_byte_len(n);
+    pkcs1_pad_into(message, modulus_bytes, out, rng)?;
+    let c = BigUint::from_bytes_be(out).modpow(e, n);
+    write_padded_bytes(&c, modulus_bytes, out);
+    Ok(())
 }
 
+// Runs in time independent of where (or whether) the padding is malformed:
+// every byte is scanned and validity is accumulated as a mask.
 pub fn pkcs1_unpad(padded: &[u8]) -> Option<Vec<u8>> {
-    if padded.len() < 11 || padded[0] != 0x00 || padded[1] != 0x02 {
+    pkcs1_unpad_start(padded).map(|start| padded[start..].to_vec())
+}
+
+// Offset of the message within a valid padded block
+fn pkcs1_unpad_start(padded: &[u8]) -> Option<usize> {
+    if padded.len() < 11 {
         return None;
     }
 
-    let mut i = 2;
-    while i < padded.len() && padded[i] != 0x00 {
-        i += 1;
+    let mut valid = ct_is_zero(padded[0] as u32) & ct_eq_u32(padded[1] as u32, 0x02);
+
+    // Index of the first zero byte after the header, found without early exit
+    let mut looking = u32::MAX;
+    let mut zero_index = 0u32;
+    for (i, &byte) in padded.iter().enumerate().skip(2) {
+        let is_zero = ct_is_zero(byte as u32);
+        zero_index = ct_select(looking & is_zero, i as u32, zero_index);
+        looking &= !is_zero;
     }
 
-    if i >= padded.len() {
+    // A separator must exist and at least 8 padding bytes must precede it
+    valid &= !looking;
+    valid &= !ct_lt(zero_index, 10);
+
+    if valid == 0 {
         return None;
     }
+    Some(zero_index as usize + 1)
+}
+
+// EMSA-PKCS1-v1_5: 0x00 0x01 0xFF.. 0x00 || DigestInfo, for an already computed digest.
+// Fails if the modulus has no room for the DigestInfo and 8 bytes of 0xFF, e.g. a
+// 512-bit key with SHA-384
+fn emsa_pkcs1_v15_encode(
+    digest: &[u8],
+    modulus_bytes: usize,
+    hash: HashAlgorithm,
+) -> Result<Vec<u8>, RsaError> {
+    let prefix = hash.digest_info_prefix();
+    let t_len = prefix.len() + digest.len();
+    if modulus_bytes < t_len + 11 {
+        return Err(RsaError::KeyTooSmall {
+            bits: modulus_bytes * 8,
+            min: (t_len + 10) * 8 + 1,
+        });
+    }
+
+    let mut encoded = vec![0xFF; modulus_bytes];
+    encoded[0] = 0x00;
+    encoded[1] = 0x01;
+    encoded[modulus_bytes - t_len - 1] = 0x00;
+    encoded[modulus_bytes - t_len..modulus_bytes - digest.len()].copy_from_slice(prefix);
+    encoded[modulus_bytes - digest.len()..].copy_from_slice(digest);
+    Ok(encoded)
+}
+
+#[cfg(feature = "std")]
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "std")]
+fn decode_hex(hex: &str) -> Result<Vec<u8>, RsaError> {
+    let digits = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex)
+        .as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return Err(RsaError::InvalidHex);
+    }
+    let nibble = |c: u8| (c as char).to_digit(16).ok_or(RsaError::InvalidHex);
+    digits
+        .chunks_exact(2)
+        .map(|pair| Ok(((nibble(pair[0])? << 4) | nibble(pair[1])?) as u8))
+        .collect()
+}
+
+fn left_pad(bytes: &[u8], len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    out[len - bytes.len()..].copy_from_slice(bytes);
+    out
+}
+
+// Big-endian `x` right-aligned in a zeroed `len`-byte buffer, written straight from
+// the limbs. Unlike `left_pad(&x.to_bytes_be(), len)`, no intermediate buffer sized
+// by the value's leading zeros is built, which matters for decrypted plaintexts.
+pub(crate) fn to_padded_bytes(x: &BigUint, len: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_padded_bytes(x, len, &mut out);
+    out
+}
+
+// Same, into a reused buffer; whatever `out` held before is overwritten
+fn write_padded_bytes(x: &BigUint, len: usize, out: &mut Vec<u8>) {
+    debug_assert!(x.bits() <= len as u64 * 8);
+    out.clear();
+    out.resize(len, 0);
+    let mut end = len;
+    for digit in x.iter_u64_digits() {
+        let take = end.min(8);
+        out[end - take..end].copy_from_slice(&digit.to_be_bytes()[8 - take..]);
+        end -= take;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    // With tiny primes, two independent draws collide often. A prime source that
+    // repeats itself reproduces that at the smallest key size still accepted.
+    #[test]
+    fn equal_primes_are_redrawn() {
+        let mut rng = StdRng::seed_from_u64(34);
+        let e = BigUint::from(65537u32);
+        let p = generate_prime_with_rounds(256, 40, &mut rng);
+        let other = generate_prime_with_rounds(256, 40, &mut rng);
+
+        let mut draws = [&p, &p, &p, &other].into_iter().cloned();
+        let rsa = RSA::generate_with(MIN_KEY_BITS, e.clone(), &mut rng, false, |_, _| {
+            Ok(draws.next().unwrap())
+        })
+        .unwrap();
+        let (a, b) = rsa.primes().unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a * b, &p * &other);
+
+        // A source stuck on one prime gives up instead of returning n = p^2
+        let stuck = RSA::generate_with(MIN_KEY_BITS, e, &mut rng, false, |_, _| Ok(p.clone()));
+        assert!(matches!(
+            stuck,
+            Err(RsaError::KeyValidationFailed("p and q are too close"))
+        ));
+    }
+
+    #[test]
+    fn multiprime_generation_always_succeeds() {
+        let mut rng = StdRng::seed_from_u64(61);
+        for (bit_len, num_primes, runs) in [(512, 2, 40), (1024, 3, 20)] {
+            for _ in 0..runs {
+                let rsa = RSA::new_multiprime_with_rng(bit_len, num_primes, &mut rng).unwrap();
+                assert_eq!(rsa.modulus_bits(), bit_len as u64);
+                let crt = rsa.crt_params().unwrap();
+                assert_eq!(crt.primes().count(), num_primes);
+                rsa.validate().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn prime_distance_threshold() {
+        // Below 100-bit primes only p == q is too close
+        let p = BigUint::from(251u32);
+        assert!(!primes_far_apart(&p, &p, 8));
+        assert!(primes_far_apart(&p, &BigUint::from(241u32), 8));
+        let (three, five) = (BigUint::from(3u32), BigUint::from(5u32));
+        assert!(primes_far_apart(&three, &five, 2));
+
+        // At 1024 bits they must differ by more than 2^924, in either order
+        let p = (BigUint::one() << 1023u32) + 1u32;
+        let limit = BigUint::one() << 924u32;
+        let at_limit = &p + &limit;
+        let past_limit = &at_limit + 2u32;
+        assert!(!primes_far_apart(&p, &at_limit, 1024));
+        assert!(!primes_far_apart(&at_limit, &p, 1024));
+        assert!(primes_far_apart(&p, &past_limit, 1024));
+        assert!(primes_far_apart(&past_limit, &p, 1024));
+    }
+
+    #[test]
+    fn key_bytes_round_trip() {
+        let mut rng = StdRng::seed_from_u64(91);
+        let rsa = RSA::new_multiprime_with_rng(1024, 3, &mut rng).unwrap();
+        let bytes = rsa.to_bytes().unwrap();
+        let back = RSA::from_bytes(&bytes).unwrap();
+        assert_eq!(back.public_key, rsa.public_key);
+        assert_eq!(back.crt_params().unwrap().primes().count(), 3);
+
+        // A key without CRT parameters writes a prime count of zero
+        let (e, n) = rsa.public_key.clone();
+        let plain = RSA::from_components(e, rsa.private_key.0.clone(), n);
+        let back = RSA::from_bytes(&plain.to_bytes().unwrap()).unwrap();
+        assert!(back.crt_params().is_none());
+
+        // A bad version byte is a malformed blob, not an unknown algorithm
+        let mut bad = bytes.clone();
+        bad[0] ^= 0xff;
+        assert!(matches!(
+            RSA::from_bytes(&bad),
+            Err(RsaError::InvalidKeyBytes)
+        ));
+    }
+
+    #[test]
+    fn key_bytes_reject_too_many_primes() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let e = BigUint::from(65537u32);
+        let rsa = loop {
+            let primes = (0..6)
+                .map(|_| generate_prime_with_rounds(100, 40, &mut rng))
+                .collect();
+            if let Some(rsa) = RSA::from_prime_list(primes, &e) {
+                break rsa;
+            }
+        };
+        assert!(matches!(
+            rsa.to_bytes(),
+            Err(RsaError::InvalidPrimeCount { got: 6, max: 5 })
+        ));
+    }
+
+    #[test]
+    fn debug_output_redacts_secrets() {
+        let mut rng = StdRng::seed_from_u64(56);
+        let rsa = RSA::new_multiprime_with_rng(1024, 3, &mut rng).unwrap();
+        let crt = rsa.crt_params().unwrap();
+        let mut secrets = vec![rsa.private_exponent().into_owned()];
+        secrets.extend([&crt.p, &crt.q, &crt.dp, &crt.dq, &crt.qinv].map(Clone::clone));
+        for other in &crt.other_primes {
+            secrets.extend([&other.r, &other.d, &other.t].map(Clone::clone));
+        }
+
+        let debug = format!("{:?}", rsa);
+        let export = format!("{:?}", rsa.to_export());
+        let (e, n) = &rsa.public_key;
+        assert!(debug.contains(&n.to_string()) && debug.contains(&e.to_string()));
+        assert!(export.contains(&BASE64_STANDARD.encode(n.to_bytes_be())));
+        for secret in &secrets {
+            for encoded in [
+                secret.to_string(),
+                secret.to_str_radix(16),
+                BASE64_STANDARD.encode(secret.to_bytes_be()),
+            ] {
+                assert!(!debug.contains(&encoded) && !export.contains(&encoded));
+            }
+        }
+    }
+
+    #[test]
+    fn distinct_keypairs() {
+        let mut rng = StdRng::seed_from_u64(100);
+        let keys = RSA::generate_distinct_keypairs_with_rng(4, 512, &mut rng).unwrap();
+        assert_eq!(keys.len(), 4);
+        for (i, a) in keys.iter().enumerate() {
+            assert!(a.shares_modulus_with(a));
+            for b in &keys[i + 1..] {
+                assert!(!a.shares_modulus_with(b));
+                assert!(gcd(&a.public_key.1, &b.public_key.1).is_one());
+            }
+        }
+
+        let none = RSA::generate_distinct_keypairs_with_rng(0, 512, &mut rng).unwrap();
+        assert!(none.is_empty());
+        assert!(matches!(
+            RSA::generate_distinct_keypairs_with_rng(2, 256, &mut rng),
+            Err(RsaError::KeyTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn shared_modulus_ignores_the_exponents() {
+        let rsa = RSA::new_with_rng(512, &mut StdRng::seed_from_u64(101)).unwrap();
+        let n = rsa.public_key.1.clone();
+        let other = RSA::from_components(BigUint::from(3u32), BigUint::from(7u32), n);
+        assert!(rsa.shares_modulus_with(&other) && other.shares_modulus_with(&rsa));
+    }
+
+    #[test]
+    fn encrypt_batch_round_trips() {
+        let mut rng = StdRng::seed_from_u64(44);
+        let rsa = RSA::new_with_rng(1024, &mut rng).unwrap();
+        let messages: [&[u8]; 3] = [b"", b"one", &[0xff; 117]];
+        let public = rsa.public();
+        let batch = public.encrypt_batch_with_rng(&messages, &mut rng).unwrap();
+        assert_eq!(batch.len(), messages.len());
+        for (ciphertext, message) in batch.iter().zip(messages) {
+            assert_eq!(ciphertext.len(), 128);
+            assert_eq!(rsa.decrypt(ciphertext).unwrap(), message);
+        }
+        // Every message gets its own padding
+        let same = rsa.encrypt_batch(&[b"one", b"one"]).unwrap();
+        assert_ne!(same[0], same[1]);
+        assert!(rsa.encrypt_batch(&[]).unwrap().is_empty());
+
+        // One message too long fails the whole batch
+        assert!(matches!(
+            rsa.encrypt_batch(&[b"fits", &[0; 118]]),
+            Err(RsaError::MessageTooLong { got: 118, max: 117 })
+        ));
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let rsa = RSA::new_with_rng(1024, &mut StdRng::seed_from_u64(60)).unwrap();
+        let ciphertext = rsa.public().encrypt_hex("0xDEADbeef").unwrap();
+        assert_eq!(ciphertext.len(), 256);
+        let lowercase_hex = |b: u8| b.is_ascii_digit() || matches!(b, b'a'..=b'f');
+        assert!(ciphertext.bytes().all(lowercase_hex));
+        assert_eq!(rsa.decrypt_hex(&ciphertext).unwrap(), "deadbeef");
+        let upper = format!("0X{}", ciphertext.to_uppercase());
+        assert_eq!(rsa.decrypt_hex(&upper).unwrap(), "deadbeef");
+        assert_eq!(rsa.decrypt_hex(&rsa.encrypt_hex("").unwrap()).unwrap(), "");
+
+        for bad in ["abc", "0xzz", "12 34", "0x0x12"] {
+            assert!(matches!(rsa.encrypt_hex(bad), Err(RsaError::InvalidHex)));
+            assert!(matches!(rsa.decrypt_hex(bad), Err(RsaError::InvalidHex)));
+        }
+    }
+
+    #[test]
+    fn fingerprint_known_answer() {
+        // SHA-256 of the DER 30 07 02 02 0c a1 02 01 11, for the textbook n = 61 * 53
+        let public = RSA::from_public_components(BigUint::from(17u32), BigUint::from(3233u32));
+        assert_eq!(
+            public.public_key_fingerprint(),
+            "bf:b3:2b:fe:c1:97:28:79:35:9a:37:29:8c:4a:16:c5:\
+             70:a2:80:ba:90:cc:30:d5:12:ba:69:de:6d:ae:9c:99"
+        );
+
+        let rsa = RSA::new_with_rng(512, &mut StdRng::seed_from_u64(20)).unwrap();
+        let fingerprint = rsa.public_key_fingerprint();
+        assert_eq!(fingerprint, rsa.public().public_key_fingerprint());
+        assert_eq!(fingerprint.len(), 32 * 3 - 1);
+        // Any change to e or n changes the fingerprint
+        let (e, n) = rsa.public_key.clone();
+        let other_e = RSA::from_public_components(e + 2u32, n.clone());
+        let other_n = RSA::from_public_components(BigUint::from(65537u32), n + 2u32);
+        assert_ne!(other_e.public_key_fingerprint(), fingerprint);
+        assert_ne!(other_n.public_key_fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn recover_public_key_from_d() {
+        let mut rng = StdRng::seed_from_u64(82);
+        let rsa = RSA::new_with_rng(512, &mut rng).unwrap();
+        let recovered = RSA::recover_public_key_with_rng(&rsa.private_key, &mut rng).unwrap();
+        assert_eq!(recovered, rsa.public());
+        let (p, q) = rsa.primes().unwrap();
+        let from_primes = RSA::recover_public_key_from_primes(&rsa.private_key.0, p, q).unwrap();
+        assert_eq!(from_primes, rsa.public());
+
+        // e = 3 is in `COMMON_EXPONENTS`, e = 7 is not
+        let key_with = |e: u32, rng: &mut StdRng| loop {
+            let p = generate_prime_with_rounds(256, 40, rng);
+            let q = generate_prime_with_rounds(256, 40, rng);
+            if let Some(rsa) = RSA::from_primes(p, q, &BigUint::from(e)) {
+                break rsa;
+            }
+        };
+        let three = key_with(3, &mut rng);
+        let recovered = RSA::recover_public_key_with_rng(&three.private_key, &mut rng).unwrap();
+        assert_eq!(recovered.e, BigUint::from(3u32));
+        let seven = key_with(7, &mut rng);
+        assert!(matches!(
+            RSA::recover_public_key_with_rng(&seven.private_key, &mut rng),
+            Err(RsaError::KeyValidationFailed(_))
+        ));
+        let (p, q) = seven.primes().unwrap();
+        let from_primes = RSA::recover_public_key_from_primes(&seven.private_key.0, p, q).unwrap();
+        assert_eq!(from_primes.e, BigUint::from(7u32));
+
+        let n = rsa.public_key.1.clone();
+        for bad in [(BigUint::zero(), n), (BigUint::one(), BigUint::from(2u32))] {
+            assert!(matches!(
+                RSA::recover_public_key_with_rng(&bad, &mut rng),
+                Err(RsaError::KeyValidationFailed(_))
+            ));
+        }
+        assert!(matches!(
+            RSA::recover_public_key_from_primes(&seven.private_key.0, p, &BigUint::one()),
+            Err(RsaError::KeyValidationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn consistency_check() {
+        let mut rng = StdRng::seed_from_u64(45);
+        let rsa = RSA::new_with_rng(512, &mut rng).unwrap();
+        assert!(rsa.is_consistent_with_rng(&mut rng));
+        // Without the primes, as `from_components` builds it
+        let (e, n) = rsa.public_key.clone();
+        let d = rsa.private_key.0.clone();
+        let bare = RSA::from_components(e.clone(), d.clone(), n.clone());
+        assert!(bare.is_consistent_with_rng(&mut rng));
+
+        let wrong_d = RSA::from_components(e.clone(), &d + 2u32, n.clone());
+        assert!(!wrong_d.is_consistent_with_rng(&mut rng));
+        let mut mismatched = RSA::from_components(e, d, n.clone());
+        mismatched.private_key.1 = n + 2u32;
+        assert!(!mismatched.is_consistent_with_rng(&mut rng));
+        let tiny = RSA::from_components(BigUint::one(), BigUint::one(), BigUint::from(2u32));
+        assert!(!tiny.is_consistent_with_rng(&mut rng));
+    }
+
+    #[test]
+    fn decrypt_auto_detects_the_padding() {
+        let mut rng = StdRng::seed_from_u64(73);
+        let rsa = RSA::new_with_rng(1024, &mut rng).unwrap();
+        let sha256 = HashAlgorithm::Sha256;
+        for _ in 0..8 {
+            let pkcs1 = rsa.encrypt_with_rng(b"legacy", &mut rng).unwrap();
+            let (message, scheme) = rsa.decrypt_auto(&pkcs1, sha256).unwrap();
+            assert_eq!(
+                (message.as_slice(), scheme),
+                (&b"legacy"[..], PaddingScheme::Pkcs1v15)
+            );
+
+            let oaep = rsa
+                .encrypt_oaep_with_rng(b"migrated", sha256, b"", &mut rng)
+                .unwrap();
+            let (message, scheme) = rsa.decrypt_auto(&oaep, sha256).unwrap();
+            assert_eq!(
+                (message.as_slice(), scheme),
+                (&b"migrated"[..], PaddingScheme::Oaep(sha256))
+            );
+        }
+
+        // OAEP under another hash or label is neither scheme
+        let sha384 = HashAlgorithm::Sha384;
+        let other_hash = rsa
+            .encrypt_oaep_with_rng(b"x", sha384, b"", &mut rng)
+            .unwrap();
+        let labelled = rsa
+            .encrypt_oaep_with_rng(b"x", sha256, b"label", &mut rng)
+            .unwrap();
+        for ciphertext in [other_hash, labelled] {
+            assert!(matches!(
+                rsa.decrypt_auto(&ciphertext, sha256),
+                Err(RsaError::InvalidPadding)
+            ));
+        }
+        assert!(matches!(
+            rsa.decrypt_auto(&[0; 129], sha256),
+            Err(RsaError::InvalidCiphertextLength)
+        ));
+    }
+
+    #[test]
+    fn keys_parse_from_json() {
+        let rsa = RSA::new_with_rng(512, &mut StdRng::seed_from_u64(51)).unwrap();
+        let public_json = rsa.export_public_key();
+        let public: RSAPublicKey = public_json.parse().unwrap();
+        assert_eq!(public, rsa.public());
+        assert_eq!(
+            RSAPublicKey::try_from(public_json.as_str()).unwrap(),
+            public
+        );
+
+        let private_json = rsa.export_private_key();
+        let private: RSA = private_json.parse().unwrap();
+        assert_eq!(private.public_key, rsa.public_key);
+        assert_eq!(private.primes(), rsa.primes());
+        let private = RSA::try_from(private_json.as_str()).unwrap();
+        assert_eq!(private.private_key, rsa.private_key);
+
+        for bad in ["", "{}", "not json", "{\"e\": 3}"] {
+            assert!(matches!(
+                bad.parse::<RSAPublicKey>(),
+                Err(RsaError::InvalidKeyJson(_))
+            ));
+            assert!(matches!(
+                RSA::try_from(bad),
+                Err(RsaError::InvalidKeyJson(_))
+            ));
+        }
+        // Private keys are validated, so a damaged prime is caught
+        let mut value: serde_json::Value = serde_json::from_str(&private_json).unwrap();
+        value["p"] = value["q"].clone();
+        assert!(matches!(
+            value.to_string().parse::<RSA>(),
+            Err(RsaError::KeyValidationFailed(_))
+        ));
+    }
 
-    Some(padded[i + 1..].to_vec())
+    #[test]
+    fn little_endian_json_import() {
+        let rsa = RSA::new_with_rng(512, &mut StdRng::seed_from_u64(88)).unwrap();
+        // Every base64 component of an export, rewritten least significant byte first
+        let to_le = |json: &str| {
+            let mut value: serde_json::Value = serde_json::from_str(json).unwrap();
+            for (_, component) in value.as_object_mut().unwrap() {
+                let be = BASE64_STANDARD.decode(component.as_str().unwrap()).unwrap();
+                let le = BigUint::from_bytes_be(&be).to_bytes_le();
+                *component = BASE64_STANDARD.encode(le).into();
+            }
+            value.to_string()
+        };
+        let le = ByteOrder::LittleEndian;
+        assert_eq!(le.to_biguint(&[1, 2]), BigUint::from(0x0201u32));
+        assert_eq!(
+            ByteOrder::default().to_biguint(&[1, 2]),
+            BigUint::from(0x0102u32)
+        );
+
+        let public_json = to_le(&rsa.export_public_key());
+        let public = RSA::import_public_key_with_byte_order(&public_json, le).unwrap();
+        assert_eq!(public, rsa.public());
+        let private_json = to_le(&rsa.export_private_key());
+        let private = RSA::import_private_key_with_byte_order(&private_json, le).unwrap();
+        assert_eq!(private.private_key, rsa.private_key);
+
+        // Read the wrong way round, this modulus ends in its even top byte, and the
+        // private key fails validation
+        assert!(matches!(
+            RSA::import_public_key(&public_json),
+            Err(RsaError::KeyValidationFailed(_))
+        ));
+        assert!(matches!(
+            RSA::import_private_key_checked(&private_json),
+            Err(RsaError::KeyValidationFailed(_))
+        ));
+        let big_endian = rsa.export_private_key();
+        assert!(RSA::import_private_key_with_byte_order(&big_endian, le).is_err());
+    }
+
+    #[test]
+    fn new_with_progress_reports_both_primes() {
+        let mut found = 0;
+        let rsa = RSA::new_with_progress(512, |event| {
+            match event {
+                PrimeGenEvent::PrimeFound => found += 1,
+                PrimeGenEvent::CandidateTested => {}
+            }
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(rsa.public_key.1.bits(), 512);
+        assert!(found >= 2);
+        assert!(rsa.is_consistent());
+    }
+
+    #[test]
+    fn new_with_progress_can_be_cancelled() {
+        let mut calls = 0;
+        let result = RSA::new_with_progress(512, |_| {
+            calls += 1;
+            ControlFlow::Break(())
+        });
+        assert!(matches!(result, Err(RsaError::Cancelled)));
+        assert_eq!(calls, 1);
+    }
+
+    // With e = 3 every prime r = 1 mod 3 is unusable, so one is skipped on its own
+    // instead of costing a whole attempt.
+    #[test]
+    fn primes_sharing_a_factor_with_e_are_redrawn() {
+        let mut rng = StdRng::seed_from_u64(94);
+        let e = BigUint::from(3u32);
+        let mut prime_mod_3 = |residue: u32| loop {
+            let r = generate_prime_with_rounds(256, 40, &mut rng);
+            if &r % 3u32 == BigUint::from(residue) {
+                break r;
+            }
+        };
+        let (bad, p, q) = (prime_mod_3(1), prime_mod_3(2), prime_mod_3(2));
+
+        let mut draws = [&bad, &p, &bad, &q].into_iter().cloned();
+        let rsa = RSA::generate_with(MIN_KEY_BITS, e.clone(), &mut rng, false, |_, _| {
+            Ok(draws.next().unwrap())
+        })
+        .unwrap();
+        assert_eq!(rsa.public_key.1, &p * &q);
+
+        let stuck = RSA::generate_with(MIN_KEY_BITS, e, &mut rng, false, |_, _| Ok(bad.clone()));
+        assert!(matches!(stuck, Err(RsaError::NonInvertibleExponent)));
+    }
 }
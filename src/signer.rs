@@ -0,0 +1,114 @@
+// Incremental RSASSA-PKCS1-v1_5 signing and verification for inputs too large to
+// hold in memory:
+//
+//     let mut signer = rsa.signer(HashAlgorithm::Sha256);
+//     std::io::copy(&mut file, &mut signer)?;
+//     let signature = signer.finalize()?;
+//
+// The data is only hashed as it arrives; the RSA operation runs once, at
+// `finalize`. Signatures are identical to `sign` / `verify` over the whole input.
+
+#[cfg(feature = "std")]
+use std::io;
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng};
+
+use crate::error::RsaError;
+use crate::hash::{HashAlgorithm, Hasher};
+use crate::rsa::{RSA, RSAPublicKey};
+
+pub struct Signer<'a> {
+    key: &'a RSA,
+    hash: HashAlgorithm,
+    hasher: Hasher,
+}
+
+impl Signer<'_> {
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn finalize(self) -> Result<Vec<u8>, RsaError> {
+        self.finalize_with_rng(&mut thread_rng())
+    }
+
+    // `rng` supplies the blinding factor, as in `RSA::sign_with_rng`
+    pub fn finalize_with_rng<R: Rng + CryptoRng>(self, rng: &mut R) -> Result<Vec<u8>, RsaError> {
+        let digest = self.hasher.finalize();
+        self.key.sign_digest_with_rng(&digest, self.hash, rng)
+    }
+}
+
+// Holds the signature from the start, so a mismatch only shows up at `finalize`
+pub struct Verifier {
+    key: RSAPublicKey,
+    hash: HashAlgorithm,
+    hasher: Hasher,
+    signature: Vec<u8>,
+}
+
+impl Verifier {
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    pub fn finalize(self) -> bool {
+        let digest = self.hasher.finalize();
+        self.key.verify_digest(&digest, &self.signature, self.hash)
+    }
+}
+
+// `io::copy(&mut reader, &mut signer)` feeds a whole stream in
+#[cfg(feature = "std")]
+impl io::Write for Signer<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for Verifier {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RSA {
+    pub fn signer(&self, hash: HashAlgorithm) -> Signer<'_> {
+        Signer {
+            key: self,
+            hash,
+            hasher: Hasher::new(hash),
+        }
+    }
+
+    pub fn verifier(&self, hash: HashAlgorithm, signature: &[u8]) -> Verifier {
+        self.public().verifier(hash, signature)
+    }
+}
+
+impl RSAPublicKey {
+    pub fn verifier(&self, hash: HashAlgorithm, signature: &[u8]) -> Verifier {
+        Verifier {
+            key: self.clone(),
+            hash,
+            hasher: Hasher::new(hash),
+            signature: signature.to_vec(),
+        }
+    }
+}
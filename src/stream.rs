@@ -0,0 +1,136 @@
+use std::io::{self, Read, Write};
+
+use rand::{Rng, thread_rng};
+
+use crate::aes::{AesKey, NONCE_LEN, TAG_LEN, gcm_decrypt, gcm_encrypt};
+use crate::error::RsaError;
+use crate::rsa::{RSA, RSAPublicKey};
+
+// Streaming hybrid encryption: one ephemeral AES-256-GCM key for the whole stream.
+// Header: [enc_key_len: u16 BE][enc_key][nonce_prefix: 8]
+// Then chunks: [ct_len: u32 BE][ciphertext || tag: 16]
+//
+// Chunk i uses nonce = nonce_prefix || i (u32 BE). Every chunk holds CHUNK_LEN bytes
+// of plaintext except the last, which is shorter (possibly empty); the last-chunk flag
+// is bound in as AAD, so truncating or extending the stream fails authentication.
+
+const CHUNK_LEN: usize = 64 * 1024;
+const NONCE_PREFIX_LEN: usize = NONCE_LEN - 4;
+
+const AAD_MORE: &[u8] = &[0x00];
+const AAD_LAST: &[u8] = &[0x01];
+
+impl RSAPublicKey {
+    pub fn encrypt_stream<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut rng = thread_rng();
+        let key = AesKey::random(&mut rng);
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        rng.fill(&mut prefix);
+
+        let enc_key = self.wrap_key(&key.0).map_err(invalid_data)?;
+        writer.write_all(&(enc_key.len() as u16).to_be_bytes())?;
+        writer.write_all(&enc_key)?;
+        writer.write_all(&prefix)?;
+
+        let mut buf = vec![0u8; CHUNK_LEN];
+        let mut counter = 0u32;
+        loop {
+            let filled = read_full(&mut reader, &mut buf)?;
+            let last = filled < CHUNK_LEN;
+            let aad = if last { AAD_LAST } else { AAD_MORE };
+
+            let body = gcm_encrypt(&key.0, &chunk_nonce(&prefix, counter), &buf[..filled], aad);
+            writer.write_all(&(body.len() as u32).to_be_bytes())?;
+            writer.write_all(&body)?;
+
+            if last {
+                return writer.flush();
+            }
+            counter = next_counter(counter)?;
+        }
+    }
+}
+
+impl RSA {
+    pub fn encrypt_stream<R: Read, W: Write>(&self, reader: R, writer: W) -> io::Result<()> {
+        self.public().encrypt_stream(reader, writer)
+    }
+
+    pub fn decrypt_stream<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut len_bytes = [0u8; 2];
+        reader.read_exact(&mut len_bytes)?;
+        let mut enc_key = vec![0u8; u16::from_be_bytes(len_bytes) as usize];
+        reader.read_exact(&mut enc_key)?;
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        reader.read_exact(&mut prefix)?;
+
+        let key = self.unwrap_aes_key(&enc_key).map_err(invalid_data)?;
+
+        let mut counter = 0u32;
+        loop {
+            let mut frame_len = [0u8; 4];
+            reader.read_exact(&mut frame_len)?;
+            let frame_len = u32::from_be_bytes(frame_len) as usize;
+            if !(TAG_LEN..=CHUNK_LEN + TAG_LEN).contains(&frame_len) {
+                return Err(invalid_data(RsaError::InvalidCiphertextLength));
+            }
+
+            let mut body = vec![0u8; frame_len];
+            reader.read_exact(&mut body)?;
+            let last = frame_len - TAG_LEN < CHUNK_LEN;
+            let aad = if last { AAD_LAST } else { AAD_MORE };
+
+            let chunk = gcm_decrypt(&key.0, &chunk_nonce(&prefix, counter), &body, aad)
+                .ok_or_else(|| invalid_data(RsaError::DecryptionFailed))?;
+            writer.write_all(&chunk)?;
+
+            if last {
+                // Anything after the final chunk was not produced by encrypt_stream
+                if reader.read(&mut [0u8; 1])? != 0 {
+                    return Err(invalid_data(RsaError::InvalidCiphertextLength));
+                }
+                return writer.flush();
+            }
+            counter = next_counter(counter)?;
+        }
+    }
+}
+
+// Like read_exact, but a short count at EOF is not an error
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+fn next_counter(counter: u32) -> io::Result<u32> {
+    counter
+        .checked_add(1)
+        .ok_or_else(|| io::Error::other("stream too long for a single key"))
+}
+
+fn invalid_data(err: RsaError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,50 @@
+// Browser-facing wrappers. Keys cross the boundary as the JSON produced by
+// `export_public_key` / `export_private_key`, data as byte arrays, and errors
+// as their display strings, so every signature maps directly onto
+// wasm-bindgen types (`String`, `Uint8Array`, `Result<_, String>`).
+//
+// The `wasm` feature also turns on `getrandom`'s `js` backend, so `thread_rng`
+// draws from `crypto.getRandomValues`. See examples/wasm for a build and a page
+// that calls these.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::rsa::RSA;
+
+#[wasm_bindgen]
+pub struct KeyPair {
+    public_key: String,
+    private_key: String,
+}
+
+#[wasm_bindgen]
+impl KeyPair {
+    pub fn public_key(&self) -> String {
+        self.public_key.clone()
+    }
+
+    pub fn private_key(&self) -> String {
+        self.private_key.clone()
+    }
+}
+
+#[wasm_bindgen]
+pub fn generate_keypair(bits: usize) -> Result<KeyPair, String> {
+    let rsa = RSA::new(bits).map_err(|e| e.to_string())?;
+    Ok(KeyPair {
+        public_key: rsa.export_public_key(),
+        private_key: rsa.export_private_key(),
+    })
+}
+
+#[wasm_bindgen]
+pub fn encrypt(public_key_json: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let public_key = RSA::import_public_key(public_key_json).map_err(|e| e.to_string())?;
+    public_key.encrypt(data).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+pub fn decrypt(private_key_json: &str, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    let rsa = RSA::import_private_key_checked(private_key_json).map_err(|e| e.to_string())?;
+    rsa.decrypt(ciphertext).map_err(|e| e.to_string())
+}
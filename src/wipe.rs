@@ -0,0 +1,37 @@
+// Best-effort wiping of secret big integers.
+//
+// `BigUint` does not expose its digit buffer, so we overwrite it through
+// `assign_from_slice` with zeros. The top digit is kept non-zero so the value
+// is not normalized (which would truncate and free the buffer before the
+// zeros are observed); `black_box` then keeps the stores from being optimized
+// away, and only after that is the buffer released. Spare capacity left over
+// from earlier arithmetic is out of reach and is not wiped.
+
+use alloc::vec;
+use core::hint::black_box;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+pub(crate) fn wipe_biguint(x: &mut BigUint) {
+    let len = x.iter_u32_digits().len();
+    if len == 0 {
+        return;
+    }
+
+    let mut zeros = vec![0u32; len];
+    zeros[len - 1] = 1;
+    x.assign_from_slice(&zeros);
+    black_box(&*x);
+    x.set_zero();
+}
+
+pub(crate) fn wipe_bytes(buf: &mut [u8]) {
+    buf.fill(0);
+    black_box(&*buf);
+}
+
+pub(crate) fn wipe_limbs(buf: &mut [u64]) {
+    buf.fill(0);
+    black_box(&*buf);
+}
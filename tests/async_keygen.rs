@@ -0,0 +1,48 @@
+// `RSA::new_async` driven by a minimal executor, since the crate depends on no
+// async runtime.
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+
+use rsa_rust::{RSA, RsaError};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn resolves_to_a_working_key() {
+    let rsa = block_on(RSA::new_async(2048));
+    let rsa = rsa.unwrap();
+    assert_eq!(rsa.modulus_bits(), 2048);
+    assert!(rsa.validate().is_ok());
+    assert_eq!(
+        rsa.decrypt(&rsa.encrypt(b"async").unwrap()).unwrap(),
+        b"async"
+    );
+}
+
+#[test]
+fn reports_errors_from_generation() {
+    let result = block_on(RSA::new_async(64));
+    assert!(matches!(result, Err(RsaError::KeyTooSmall { .. })));
+}
@@ -0,0 +1,81 @@
+// RsaKeyGenBuilder: Miller-Rabin round counts below the recommended minimum are
+// refused, and the progress callback sees every prime and can cancel generation.
+
+use std::ops::ControlFlow;
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rsa_rust::prime::recommended_rounds;
+use rsa_rust::{PrimeGenEvent, RsaError, RsaKeyGenBuilder};
+
+fn builder(seed: u64) -> RsaKeyGenBuilder<StdRng> {
+    RsaKeyGenBuilder::new()
+        .bits(512)
+        .rng(StdRng::seed_from_u64(seed))
+}
+
+#[test]
+fn too_few_rounds_are_rejected() {
+    let min = recommended_rounds(256);
+    for rounds in [0, 1, min - 1] {
+        match builder(1).miller_rabin_rounds(rounds).generate() {
+            Err(RsaError::TooFewRounds { got, min: limit }) => {
+                assert_eq!((got, limit), (rounds, min))
+            }
+            other => panic!("expected TooFewRounds, got {:?}", other.map(|_| ())),
+        }
+    }
+    for rounds in [min, min + 10] {
+        let rsa = builder(2).miller_rabin_rounds(rounds).generate().unwrap();
+        rsa.validate().unwrap();
+    }
+}
+
+#[test]
+fn progress_reports_every_prime() {
+    let mut events = Vec::new();
+    let rsa = builder(3)
+        .progress(|event| {
+            events.push(event);
+            ControlFlow::Continue(())
+        })
+        .generate()
+        .unwrap();
+    assert_eq!(rsa.modulus_bits(), 512);
+    let found = events
+        .iter()
+        .filter(|&&e| e == PrimeGenEvent::PrimeFound)
+        .count();
+    assert_eq!(found, 2);
+    assert_eq!(events.last(), Some(&PrimeGenEvent::PrimeFound));
+
+    // The same seed gives the same key with or without a callback
+    let plain = builder(3).generate().unwrap();
+    assert_eq!(plain.export_public_key(), rsa.export_public_key());
+}
+
+#[test]
+fn progress_can_cancel() {
+    let mut calls = 0;
+    let cancelled = builder(4)
+        .progress(|_| {
+            calls += 1;
+            ControlFlow::Break(())
+        })
+        .generate();
+    assert!(matches!(cancelled, Err(RsaError::Cancelled)));
+    assert_eq!(calls, 1);
+
+    // Safe primes report each prime once, and can be cancelled after the first
+    let mut found = 0;
+    let cancelled = builder(5)
+        .safe_primes(true)
+        .progress(|event| {
+            assert_eq!(event, PrimeGenEvent::PrimeFound);
+            found += 1;
+            ControlFlow::Break(())
+        })
+        .generate();
+    assert!(matches!(cancelled, Err(RsaError::Cancelled)));
+    assert_eq!(found, 1);
+}
@@ -0,0 +1,37 @@
+// Private exponents are derived modulo Carmichael's lambda(n) = lcm(p - 1, q - 1),
+// as OpenSSL does, rather than modulo Euler's phi(n).
+
+use num_bigint::BigUint;
+use rsa_rust::{HashAlgorithm, RSA, RsaKeyGenBuilder};
+
+fn check_lambda_exponent(rsa: &RSA) {
+    let lambda = rsa.lambda().unwrap();
+    let d = rsa.private_exponent();
+    assert!(*d < lambda);
+    assert_eq!(&rsa.public_key.0 * &*d % &lambda, BigUint::from(1u32));
+    // lambda is a proper divisor of phi, since p - 1 and q - 1 are both even
+    assert!(lambda < rsa.phi().unwrap());
+
+    let ciphertext = rsa.encrypt(b"carmichael").unwrap();
+    assert_eq!(rsa.decrypt(&ciphertext).unwrap(), b"carmichael");
+    let signature = rsa.sign(b"carmichael", HashAlgorithm::Sha256).unwrap();
+    assert!(rsa.verify(b"carmichael", &signature, HashAlgorithm::Sha256));
+    rsa.validate().unwrap();
+}
+
+#[test]
+fn generated_keys_use_lambda() {
+    check_lambda_exponent(&RSA::new(2048).unwrap());
+    check_lambda_exponent(&RSA::new_multiprime(2048, 3).unwrap());
+    let e3 = RsaKeyGenBuilder::new().bits(1024).exponent(3u32).generate();
+    check_lambda_exponent(&e3.unwrap());
+}
+
+#[test]
+fn crt_only_keys_recompute_the_same_exponent() {
+    let rsa = RSA::new(2048).unwrap();
+    let mut crt_only = rsa.clone();
+    crt_only.discard_private_exponent().unwrap();
+    assert_eq!(*crt_only.private_exponent(), rsa.private_key.0);
+    check_lambda_exponent(&crt_only);
+}
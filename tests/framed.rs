@@ -0,0 +1,99 @@
+// Framed ciphertexts: every scheme round trips through the header it writes, and
+// damaged, truncated or unknown frames fail with the documented error.
+
+use rsa_rust::{HashAlgorithm, PaddingScheme, RSA, RsaError};
+
+const SCHEMES: [PaddingScheme; 3] = [
+    PaddingScheme::Pkcs1v15,
+    PaddingScheme::Oaep(HashAlgorithm::Sha256),
+    PaddingScheme::Oaep(HashAlgorithm::Sha384),
+];
+
+fn key() -> RSA {
+    RSA::new(1024).unwrap()
+}
+
+#[test]
+fn framed_round_trips() {
+    let rsa = key();
+    for (scheme, ids) in SCHEMES.into_iter().zip([[1, 0], [2, 1], [2, 2]]) {
+        let framed = rsa
+            .public()
+            .encrypt_framed(b"stored secret", scheme)
+            .unwrap();
+        assert_eq!(&framed[..4], b"RSAF");
+        assert_eq!(framed[4], 1);
+        assert_eq!(framed[5..7], ids);
+        assert_eq!(framed.len(), 7 + 128);
+        assert_eq!(rsa.decrypt_framed(&framed).unwrap(), b"stored secret");
+
+        let framed = rsa.encrypt_framed(b"", scheme).unwrap();
+        assert_eq!(rsa.decrypt_framed(&framed).unwrap(), b"");
+    }
+}
+
+#[test]
+fn bad_version_is_unsupported() {
+    let rsa = key();
+    let mut framed = rsa.encrypt_framed(b"x", SCHEMES[1]).unwrap();
+    for version in [0, 2, 0xff] {
+        framed[4] = version;
+        assert!(matches!(
+            rsa.decrypt_framed(&framed),
+            Err(RsaError::UnsupportedAlgorithm)
+        ));
+    }
+}
+
+#[test]
+fn bad_scheme_ids_are_rejected() {
+    let rsa = key();
+    let framed = rsa.encrypt_framed(b"x", SCHEMES[0]).unwrap();
+    let with_ids = |padding, hash| {
+        let mut framed = framed.clone();
+        framed[5] = padding;
+        framed[6] = hash;
+        rsa.decrypt_framed(&framed)
+    };
+    // PKCS#1 v1.5 takes no hash
+    assert!(matches!(with_ids(1, 1), Err(RsaError::InvalidFrame)));
+    // Unknown paddings and hashes come from a newer writer
+    assert!(matches!(
+        with_ids(3, 0),
+        Err(RsaError::UnsupportedAlgorithm)
+    ));
+    assert!(matches!(
+        with_ids(2, 0),
+        Err(RsaError::UnsupportedAlgorithm)
+    ));
+    assert!(matches!(
+        with_ids(2, 3),
+        Err(RsaError::UnsupportedAlgorithm)
+    ));
+}
+
+#[test]
+fn truncated_or_damaged_frames_are_rejected() {
+    let rsa = key();
+    let framed = rsa.encrypt_framed(b"x", SCHEMES[1]).unwrap();
+    for len in [0, 3, 6, 7, 8, framed.len() - 1] {
+        assert!(matches!(
+            rsa.decrypt_framed(&framed[..len]),
+            Err(RsaError::InvalidFrame)
+        ));
+    }
+    let mut extended = framed.clone();
+    extended.push(0);
+    assert!(matches!(
+        rsa.decrypt_framed(&extended),
+        Err(RsaError::InvalidFrame)
+    ));
+
+    let mut bad_magic = framed.clone();
+    bad_magic[0] ^= 1;
+    assert!(matches!(
+        rsa.decrypt_framed(&bad_magic),
+        Err(RsaError::InvalidFrame)
+    ));
+    assert!(rsa.decrypt_framed(&framed).is_ok());
+}
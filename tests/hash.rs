@@ -0,0 +1,103 @@
+// SHA-256 and SHA-384 against the FIPS 180-4 examples, and MGF1 against outputs
+// computed independently (the SHA-256 "bar" case is the common published one).
+
+use rsa_rust::{HashAlgorithm, mgf1};
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// One-block, empty and two-block (448-bit) messages
+const TWO_BLOCK: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+#[test]
+fn sha256_known_answers() {
+    let cases: [(&[u8], &str); 3] = [
+        (
+            b"abc",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            b"",
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            TWO_BLOCK,
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ];
+    for (message, expected) in cases {
+        assert_eq!(HashAlgorithm::Sha256.digest(message), hex(expected));
+    }
+}
+
+#[test]
+fn sha384_known_answers() {
+    let cases: [(&[u8], &str); 3] = [
+        (
+            b"abc",
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded163\
+             1a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+        ),
+        (
+            b"",
+            "38b060a751ac96384cd9327eb1b1e36a21fdb71114be0743\
+             4c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b",
+        ),
+        (
+            TWO_BLOCK,
+            "3391fdddfc8dc7393707a65b1b4709397cf8b1d162af05ab\
+             fe8f450de5f36bc6b0455a8520bc4e6f5fe95b1fe3c8452b",
+        ),
+    ];
+    for (message, expected) in cases {
+        assert_eq!(HashAlgorithm::Sha384.digest(message), hex(expected));
+    }
+}
+
+#[test]
+fn mgf1_known_answers() {
+    let cases = [
+        (HashAlgorithm::Sha256, "foo", 3, "3bdaba"),
+        (
+            HashAlgorithm::Sha256,
+            "bar",
+            50,
+            "382576a7841021cc28fc4c0948753fb8312090cea942ea4c4e735d10dc724b15\
+             5f9f6069f289d61daca0cb814502ef04eae1",
+        ),
+        (HashAlgorithm::Sha384, "foo", 3, "997217"),
+        (
+            HashAlgorithm::Sha384,
+            "bar",
+            50,
+            "301f6d57b4b67ac5d327aa8e564b1254b9a7828e300913d8486b2628e1d62285\
+             fb517d0b83f401e15f4d0d6fda8a9b9c8fbd",
+        ),
+    ];
+    for (hash, seed, length, expected) in cases {
+        assert_eq!(
+            mgf1(seed.as_bytes(), length, hash),
+            hex(expected),
+            "{hash:?} {seed}"
+        );
+    }
+}
+
+#[test]
+fn mgf1_lengths() {
+    for hash in [HashAlgorithm::Sha256, HashAlgorithm::Sha384] {
+        let long = mgf1(b"seed", 200, hash);
+        assert_eq!(long.len(), 200);
+        // The first block is hash(seed || 0), and shorter masks are prefixes
+        let mut first = b"seed".to_vec();
+        first.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(long[..hash.output_len()], hash.digest(&first));
+        for length in [0, 1, hash.output_len(), hash.output_len() + 1] {
+            assert_eq!(mgf1(b"seed", length, hash), long[..length]);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// Hybrid RSA-OAEP + AES-256-GCM sealing: round trips across sizes, and every part
+// of a sealed blob checked for tampering. Envelopes for several recipients open
+// for each of them and for nobody else. Wrapped keys must be exactly the
+// modulus size, and sealed values come back as they went in.
+
+use rsa_rust::{RSA, RsaError, encrypt_to_recipients};
+use serde::{Deserialize, Serialize};
+
+fn key() -> RSA {
+    RSA::new(2048).unwrap()
+}
+
+#[test]
+fn seal_round_trips() {
+    let rsa = key();
+    for len in [0, 1, 15, 16, 17, 1000, 100_000] {
+        let plaintext: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        let sealed = rsa.seal(&plaintext).unwrap();
+        // length prefix, wrapped key, nonce, ciphertext and tag
+        assert_eq!(sealed.len(), 2 + 256 + 12 + len + 16);
+        assert_eq!(rsa.unseal(&sealed).unwrap(), plaintext);
+        assert_eq!(
+            rsa.public().seal(&plaintext).map(|s| s.len()).unwrap(),
+            sealed.len()
+        );
+    }
+}
+
+#[test]
+fn tampered_seals_are_rejected() {
+    let rsa = key();
+    let sealed = rsa.seal(b"attack at dawn").unwrap();
+    let body_start = 2 + 256 + 12;
+
+    // The wrapped key, the nonce, the ciphertext and the tag
+    for index in [10, 2 + 256 + 3, body_start, sealed.len() - 1] {
+        let mut tampered = sealed.clone();
+        tampered[index] ^= 0x01;
+        assert!(rsa.unseal(&tampered).is_err(), "byte {} flipped", index);
+    }
+    for index in [2 + 256 + 3, body_start, sealed.len() - 1] {
+        let mut tampered = sealed.clone();
+        tampered[index] ^= 0x80;
+        assert!(matches!(
+            rsa.unseal(&tampered),
+            Err(RsaError::DecryptionFailed)
+        ));
+    }
+
+    for len in [0, 1, 2, 100, body_start, sealed.len() - 1] {
+        assert!(rsa.unseal(&sealed[..len]).is_err(), "truncated to {}", len);
+    }
+    let mut extended = sealed.clone();
+    extended.push(0);
+    assert!(rsa.unseal(&extended).is_err());
+
+    // A blob sealed for one key does not open under another
+    assert!(key().unseal(&sealed).is_err());
+}
+
+#[test]
+fn envelope_opens_for_every_recipient() {
+    // Mixed sizes, and two keys of the same size so the trial unwrap is exercised
+    let recipients = [RSA::new(1024).unwrap(), key(), RSA::new(1024).unwrap()];
+    let public: Vec<_> = recipients.iter().map(|rsa| rsa.public()).collect();
+    let payload = b"one body, three keys";
+
+    let envelope = encrypt_to_recipients(payload, &public).unwrap();
+    assert_eq!(envelope.wrapped_keys.len(), recipients.len());
+    assert_eq!(envelope.wrapped_keys[1].len(), 256);
+    assert_eq!(envelope.ciphertext.len(), 12 + payload.len() + 16);
+    for rsa in &recipients {
+        assert_eq!(rsa.open_envelope(&envelope).unwrap(), payload);
+    }
+
+    let empty = encrypt_to_recipients(b"", &public).unwrap();
+    for rsa in &recipients {
+        assert_eq!(rsa.open_envelope(&empty).unwrap(), b"");
+    }
+}
+
+#[test]
+fn envelope_rejects_non_recipients() {
+    let recipients = [RSA::new(1024).unwrap(), key()];
+    let public: Vec<_> = recipients.iter().map(|rsa| rsa.public()).collect();
+    let envelope = encrypt_to_recipients(b"not for you", &public).unwrap();
+
+    // Same sizes as the recipients, and a size none of the wrapped keys has
+    for outsider in [RSA::new(1024).unwrap(), key(), RSA::new(1536).unwrap()] {
+        assert!(matches!(
+            outsider.open_envelope(&envelope),
+            Err(RsaError::DecryptionFailed)
+        ));
+    }
+
+    let mut tampered = envelope.clone();
+    *tampered.ciphertext.last_mut().unwrap() ^= 0x01;
+    assert!(matches!(
+        recipients[0].open_envelope(&tampered),
+        Err(RsaError::DecryptionFailed)
+    ));
+
+    let mut truncated = envelope.clone();
+    truncated.ciphertext.truncate(5);
+    assert!(matches!(
+        recipients[0].open_envelope(&truncated),
+        Err(RsaError::InvalidCiphertextLength)
+    ));
+}
+
+#[test]
+fn envelope_needs_keys_that_fit_an_aes_key() {
+    // OAEP with SHA-256 needs 66 bytes of overhead plus the 32-byte key
+    let small = RSA::new(512).unwrap().public();
+    let public = [key().public(), small];
+    assert!(matches!(
+        encrypt_to_recipients(b"payload", &public),
+        Err(RsaError::MessageTooLong { got: 32, max: 0 })
+    ));
+
+    // An envelope for nobody carries no wrapped keys and opens for nobody
+    let envelope = encrypt_to_recipients(b"payload", &[]).unwrap();
+    assert!(envelope.wrapped_keys.is_empty());
+    assert!(matches!(
+        key().open_envelope(&envelope),
+        Err(RsaError::DecryptionFailed)
+    ));
+}
+
+#[test]
+fn wrapped_keys_round_trip() {
+    let rsa = key();
+    for len in [0, 16, 32, 190] {
+        let secret: Vec<u8> = (0..len).map(|i| i as u8 ^ 0x5a).collect();
+        let wrapped = rsa.public().wrap_key(&secret).unwrap();
+        assert_eq!(wrapped.len(), 256);
+        assert_eq!(rsa.unwrap_key(&wrapped).unwrap(), secret);
+        assert_eq!(
+            rsa.unwrap_key(&rsa.wrap_key(&secret).unwrap()).unwrap(),
+            secret
+        );
+    }
+    assert!(matches!(
+        rsa.wrap_key(&[0; 191]),
+        Err(RsaError::MessageTooLong { got: 191, max: 190 })
+    ));
+}
+
+#[test]
+fn unwrap_key_rejects_other_lengths() {
+    let rsa = key();
+    let wrapped = rsa.wrap_key(&[7; 32]).unwrap();
+    // Even a leading zero added in front, which leaves the value alone, is refused
+    let mut long = vec![0];
+    long.extend_from_slice(&wrapped);
+    for bad in [&wrapped[..255], &long[..], &[]] {
+        assert!(matches!(
+            rsa.unwrap_key(bad),
+            Err(RsaError::InvalidCiphertextLength)
+        ));
+    }
+    assert!(key().unwrap_key(&wrapped).is_err());
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Credentials {
+    user: String,
+    token: Vec<u8>,
+    expires: Option<u64>,
+}
+
+#[test]
+fn values_round_trip() {
+    let rsa = key();
+    let value = Credentials {
+        user: "alice".into(),
+        token: vec![0, 1, 2, 255],
+        expires: Some(1_700_000_000),
+    };
+    let sealed = rsa.public().encrypt_value(&value).unwrap();
+    assert_eq!(rsa.decrypt_value::<Credentials>(&sealed).unwrap(), value);
+    // The blob is an ordinary seal of the JSON
+    let json = rsa.unseal(&sealed).unwrap();
+    assert_eq!(serde_json::from_slice::<Credentials>(&json).unwrap(), value);
+
+    let sealed = rsa.encrypt_value(&[1u32, 2, 3]).unwrap();
+    assert_eq!(rsa.decrypt_value::<Vec<u32>>(&sealed).unwrap(), [1, 2, 3]);
+    let sealed = rsa.encrypt_value(&()).unwrap();
+    rsa.decrypt_value::<()>(&sealed).unwrap();
+}
+
+#[test]
+fn tampered_or_mistyped_values_are_rejected() {
+    let rsa = key();
+    let sealed = rsa.encrypt_value(&("secret", 42u8)).unwrap();
+
+    // Authentication comes first, so tampering is never a parse error
+    for i in [0, 2, 260, sealed.len() - 1] {
+        let mut tampered = sealed.clone();
+        tampered[i] ^= 0x01;
+        match rsa.decrypt_value::<(String, u8)>(&tampered) {
+            Err(RsaError::InvalidValue(_)) | Ok(_) => panic!("byte {} was not caught", i),
+            Err(_) => {}
+        }
+    }
+    assert!(matches!(
+        rsa.decrypt_value::<(String, u8)>(&sealed[..sealed.len() - 1]),
+        Err(RsaError::DecryptionFailed)
+    ));
+
+    // An intact blob holding the wrong type is
+    assert!(matches!(
+        rsa.decrypt_value::<Credentials>(&sealed),
+        Err(RsaError::InvalidValue(_))
+    ));
+    assert_eq!(
+        rsa.decrypt_value::<(String, u8)>(&sealed).unwrap(),
+        ("secret".to_string(), 42)
+    );
+}
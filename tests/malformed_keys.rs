@@ -0,0 +1,205 @@
+// Private keys with out-of-range primes or exponents, from each import path. Every
+// one has to be rejected with an error; deriving d or the CRT exponents from them
+// used to underflow or divide by zero.
+
+use base64::prelude::*;
+use num_bigint::BigUint;
+use rsa_rust::{RSA, RsaError};
+use serde_json::Value;
+
+fn key() -> RSA {
+    RSA::new(1024).unwrap()
+}
+
+fn b64(x: &BigUint) -> Value {
+    Value::String(BASE64_STANDARD.encode(x.to_bytes_be()))
+}
+
+// PKCS#1 RSAPrivateKey ::= SEQUENCE { version, n, e, d, p, q, dp, dq, qinv }
+fn pkcs1_der(integers: &[&BigUint]) -> Vec<u8> {
+    fn tlv(tag: u8, content: &[u8], out: &mut Vec<u8>) {
+        out.push(tag);
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            let len = (content.len() as u32).to_be_bytes();
+            let skip = len.iter().take_while(|&&b| b == 0).count();
+            out.push(0x80 | (4 - skip) as u8);
+            out.extend_from_slice(&len[skip..]);
+        }
+        out.extend_from_slice(content);
+    }
+    let mut content = Vec::new();
+    for x in [&BigUint::from(0u8)]
+        .into_iter()
+        .chain(integers.iter().copied())
+    {
+        let mut bytes = x.to_bytes_be();
+        if bytes[0] & 0x80 != 0 {
+            bytes.insert(0, 0);
+        }
+        tlv(0x02, &bytes, &mut content);
+    }
+    let mut der = Vec::new();
+    tlv(0x30, &content, &mut der);
+    der
+}
+
+// The `RSA::to_bytes` layout: version, e, d, n, prime count, p, q, dp, dq, qinv
+fn key_bytes(e: &BigUint, d: &BigUint, n: &BigUint, crt: [&BigUint; 5]) -> Vec<u8> {
+    fn push(x: &BigUint, out: &mut Vec<u8>) {
+        let bytes = x.to_bytes_be();
+        out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        out.extend_from_slice(&bytes);
+    }
+    let mut out = vec![1u8];
+    [e, d, n].into_iter().for_each(|x| push(x, &mut out));
+    out.push(2);
+    crt.into_iter().for_each(|x| push(x, &mut out));
+    out
+}
+
+#[test]
+fn crt_only_json_with_zero_prime() {
+    let rsa = key();
+    let mut export: Value =
+        serde_json::from_str(&rsa.export_private_key_crt_only().unwrap()).unwrap();
+    export["p"] = b64(&BigUint::from(0u8));
+    let json = export.to_string();
+    assert!(RSA::import_private_key_checked(&json).is_err());
+    assert!(RSA::import_private_key(&json).is_err());
+    assert!(serde_json::from_str::<RSA>(&json).is_err());
+}
+
+#[test]
+fn crt_components_with_unit_prime() {
+    let rsa = key();
+    let crt = rsa.crt.as_ref().unwrap();
+    let n = &rsa.public_key.1;
+    let one = BigUint::from(1u8);
+    let result = RSA::from_crt_components(
+        rsa.public_key.0.clone(),
+        one.clone(),
+        n.clone(),
+        crt.dp.clone(),
+        crt.dq.clone(),
+        crt.qinv.clone(),
+    );
+    assert!(result.is_err());
+    let result = RSA::from_crt_components(
+        rsa.public_key.0.clone(),
+        BigUint::from(0u8),
+        crt.q.clone(),
+        crt.dp.clone(),
+        crt.dq.clone(),
+        one,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn der_and_bytes_with_zero_exponent() {
+    let rsa = key();
+    let crt = rsa.crt.as_ref().unwrap();
+    let (e, n) = &rsa.public_key;
+    let zero = BigUint::from(0u8);
+    let one = BigUint::from(1u8);
+
+    // d = 0 marks a CRT-only key, so d is derived from the (bogus) primes
+    let der = pkcs1_der(&[n, e, &zero, &one, n, &crt.dp, &crt.dq, &crt.qinv]);
+    assert!(RSA::import_private_key_der(&der).is_err());
+    let der = pkcs1_der(&[n, e, &zero, &zero, &crt.q, &crt.dp, &crt.dq, &crt.qinv]);
+    assert!(RSA::import_private_key_der(&der).is_err());
+
+    let bytes = key_bytes(e, &zero, n, [&one, n, &crt.dp, &crt.dq, &crt.qinv]);
+    assert!(RSA::from_bytes(&bytes).is_err());
+    let bytes = key_bytes(e, &zero, n, [&zero, &crt.q, &crt.dp, &crt.dq, &crt.qinv]);
+    assert!(RSA::from_bytes(&bytes).is_err());
+
+    // A well-formed blob built the same way is accepted, so the helpers are right
+    let bytes = key_bytes(e, &zero, n, [&crt.p, &crt.q, &crt.dp, &crt.dq, &crt.qinv]);
+    assert_eq!(RSA::from_bytes(&bytes).unwrap().public_key, rsa.public_key);
+    let der = pkcs1_der(&[
+        n,
+        e,
+        &rsa.private_key.0,
+        &crt.p,
+        &crt.q,
+        &crt.dp,
+        &crt.dq,
+        &crt.qinv,
+    ]);
+    assert_eq!(
+        RSA::import_private_key_der(&der).unwrap().public_key,
+        rsa.public_key
+    );
+}
+
+#[test]
+fn totients_of_bad_primes_are_errors() {
+    let rsa = key();
+    let mut crt = rsa.crt.clone().unwrap();
+    crt.p = BigUint::from(1u8);
+    assert!(crt.phi().is_err());
+    assert!(crt.lambda().is_err());
+    let mut public_only = rsa.clone();
+    public_only.crt = None;
+    assert!(public_only.phi().is_err());
+    assert!(public_only.lambda().is_err());
+}
+
+// Older exports carry d and the primes but not dp, dq and qinv, which are then
+// derived from the primes
+#[test]
+fn primes_only_json_with_bad_primes() {
+    let rsa = key();
+    let crt = rsa.crt.as_ref().unwrap();
+    let mut export: Value = serde_json::from_str(&rsa.export_private_key()).unwrap();
+    let fields = export.as_object_mut().unwrap();
+    for field in ["dp", "dq", "qinv"] {
+        fields.remove(field);
+    }
+    let n = &rsa.public_key.1;
+    let cases = [
+        (BigUint::from(0u8), crt.q.clone()),
+        (BigUint::from(1u8), n.clone()),
+        (crt.q.clone(), BigUint::from(1u8)),
+        (crt.p.clone(), &crt.q + 2u8),
+    ];
+    for (p, q) in cases {
+        export["p"] = b64(&p);
+        export["q"] = b64(&q);
+        let json = export.to_string();
+        assert!(RSA::import_private_key_checked(&json).is_err());
+        assert!(serde_json::from_str::<RSA>(&json).is_err());
+    }
+
+    export["p"] = b64(&crt.p);
+    export["q"] = b64(&crt.q);
+    let imported = RSA::import_private_key_checked(&export.to_string()).unwrap();
+    assert_eq!(imported.public_key, rsa.public_key);
+}
+
+#[test]
+fn der_with_trailing_data() {
+    let rsa = key();
+    let crt = rsa.crt.as_ref().unwrap();
+    let (e, n) = &rsa.public_key;
+    let d = &rsa.private_key.0;
+
+    let mut der = rsa.export_private_key_der().unwrap();
+    RSA::import_private_key_der(&der).unwrap();
+    der.push(0x00);
+    assert!(matches!(
+        RSA::import_private_key_der(&der),
+        Err(RsaError::InvalidDer)
+    ));
+
+    // An extra INTEGER inside the SEQUENCE, after qinv
+    let extra = BigUint::from(5u8);
+    let der = pkcs1_der(&[n, e, d, &crt.p, &crt.q, &crt.dp, &crt.dq, &crt.qinv, &extra]);
+    assert!(matches!(
+        RSA::import_private_key_der(&der),
+        Err(RsaError::InvalidDer)
+    ));
+}
@@ -0,0 +1,64 @@
+// Ciphertext and signature lengths for moduli just below, at and above a byte
+// boundary, where rounding the modulus size in bytes is easiest to get wrong.
+
+use num_bigint::BigUint;
+use rsa_rust::rsa::modulus_byte_len;
+use rsa_rust::{HashAlgorithm, RSA, RsaError};
+
+#[test]
+fn byte_len_rounds_up() {
+    for bits in 1..=256u64 {
+        let all_ones = (BigUint::from(1u8) << bits) - 1u8;
+        let top_bit_only = BigUint::from(1u8) << (bits - 1);
+        let expected = bits.div_ceil(8) as usize;
+        assert_eq!(modulus_byte_len(&all_ones), expected, "2^{} - 1", bits);
+        assert_eq!(modulus_byte_len(&top_bit_only), expected, "2^{}", bits - 1);
+    }
+    assert_eq!(modulus_byte_len(&BigUint::from(0u8)), 0);
+}
+
+#[test]
+fn keys_near_byte_boundaries() {
+    for bits in (1015..=1025).chain([2047, 2048, 2049]) {
+        let rsa = RSA::new(bits).unwrap();
+        let len = modulus_byte_len(&rsa.public_key.1);
+        assert_eq!(rsa.modulus_bits(), bits as u64);
+        assert_eq!(len, bits.div_ceil(8), "{} bits", bits);
+        assert_eq!(rsa.ciphertext_len(), len);
+        assert_eq!(rsa.signature_len(), len);
+        assert_eq!(rsa.public().ciphertext_len(), len);
+        assert_eq!(rsa.public().signature_len(), len);
+
+        let message = vec![0xC3; rsa.max_message_len()];
+        let ciphertext = rsa.encrypt(&message).unwrap();
+        assert_eq!(ciphertext.len(), len, "{} bits", bits);
+        assert_eq!(rsa.decrypt(&ciphertext).unwrap(), message);
+
+        let signature = rsa.sign(b"boundary", HashAlgorithm::Sha256).unwrap();
+        assert_eq!(signature.len(), len, "{} bits", bits);
+        assert!(rsa.verify(b"boundary", &signature, HashAlgorithm::Sha256));
+    }
+}
+
+// A 512-bit modulus holds a SHA-256 DigestInfo but not a SHA-384 one (67 bytes
+// plus 11 of padding); signing has to fail cleanly and verifying return false
+#[test]
+fn digest_info_larger_than_modulus() {
+    let rsa = RSA::new(512).unwrap();
+    let signature = rsa.sign(b"small key", HashAlgorithm::Sha256).unwrap();
+    assert!(rsa.verify(b"small key", &signature, HashAlgorithm::Sha256));
+
+    match rsa.sign(b"small key", HashAlgorithm::Sha384) {
+        Err(RsaError::KeyTooSmall { bits, min }) => assert!(bits < min, "{} >= {}", bits, min),
+        other => panic!("expected KeyTooSmall, got {:?}", other),
+    }
+    let mut signer = rsa.signer(HashAlgorithm::Sha384);
+    signer.update(b"small key");
+    assert!(signer.finalize().is_err());
+    assert!(!rsa.verify(b"small key", &signature, HashAlgorithm::Sha384));
+
+    // Public keys are not size-checked at all
+    let tiny = RSA::from_public_components(BigUint::from(65537u32), BigUint::from(3233u32));
+    assert!(!tiny.verify(b"small key", &[0x01, 0x02], HashAlgorithm::Sha256));
+    assert!(!tiny.verify(b"small key", &[0x01, 0x02], HashAlgorithm::Sha384));
+}
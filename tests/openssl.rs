@@ -0,0 +1,245 @@
+// Differential tests against the system `openssl` binary (OpenSSL 3 command line):
+// keys generated on either side, encryption and signatures produced on one side and
+// checked on the other. Enabled with `cargo test --features openssl-differential`;
+// the tests fail rather than skip if `openssl` cannot be run.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use rsa_rust::{HashAlgorithm, Kdf, RSA, RsaError};
+
+// A scratch directory per test, removed again when the test finishes
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("rsa-rust-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+// Runs `openssl args...` with `input` on stdin and returns stdout
+fn openssl(args: &[&str], input: &[u8]) -> Vec<u8> {
+    let mut child = Command::new("openssl")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run openssl");
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "openssl {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+fn path(p: &Path) -> &str {
+    p.to_str().unwrap()
+}
+
+const OAEP_SHA256: [&str; 6] = [
+    "-pkeyopt",
+    "rsa_padding_mode:oaep",
+    "-pkeyopt",
+    "rsa_oaep_md:sha256",
+    "-pkeyopt",
+    "rsa_mgf1_md:sha256",
+];
+
+#[test]
+fn encryption_matches_openssl() {
+    let dir = TempDir::new("encrypt");
+    let rsa = RSA::new(2048).unwrap();
+    let private = dir.write("key.pem", rsa.export_private_key_pem().unwrap());
+    let public = dir.write("pub.pem", rsa.export_public_key_spki_pem());
+    let message = b"differential test message";
+
+    // OpenSSL encrypts, this crate decrypts
+    let encrypt = ["pkeyutl", "-encrypt", "-pubin", "-inkey", path(&public)];
+    let ciphertext = openssl(&encrypt, message);
+    assert_eq!(rsa.decrypt(&ciphertext).unwrap(), message);
+
+    let ciphertext = openssl(&[&encrypt[..], &OAEP_SHA256].concat(), message);
+    let decrypted = rsa.decrypt_oaep(&ciphertext, HashAlgorithm::Sha256, b"");
+    assert_eq!(decrypted.unwrap(), message);
+
+    // This crate encrypts, OpenSSL decrypts
+    let decrypt = ["pkeyutl", "-decrypt", "-inkey", path(&private)];
+    let ciphertext = rsa.encrypt(message).unwrap();
+    assert_eq!(openssl(&decrypt, &ciphertext), message);
+
+    let ciphertext = rsa
+        .encrypt_oaep(message, HashAlgorithm::Sha256, b"")
+        .unwrap();
+    assert_eq!(
+        openssl(&[&decrypt[..], &OAEP_SHA256].concat(), &ciphertext),
+        message
+    );
+}
+
+#[test]
+fn oaep_labels_match_openssl() {
+    let dir = TempDir::new("label");
+    let rsa = RSA::new(2048).unwrap();
+    let private = dir.write("key.pem", rsa.export_private_key_pem().unwrap());
+    let public = dir.write("pub.pem", rsa.export_public_key_spki_pem());
+    let message = b"labelled message";
+    let label = b"context: invoices";
+    let label_opt = format!(
+        "rsa_oaep_label:{}",
+        label.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    );
+    let label_args = ["-pkeyopt", label_opt.as_str()];
+
+    let encrypt = ["pkeyutl", "-encrypt", "-pubin", "-inkey", path(&public)];
+    let ciphertext = openssl(&[&encrypt[..], &OAEP_SHA256, &label_args].concat(), message);
+    let decrypted = rsa.decrypt_oaep(&ciphertext, HashAlgorithm::Sha256, label);
+    assert_eq!(decrypted.unwrap(), message);
+    let wrong = rsa.decrypt_oaep(&ciphertext, HashAlgorithm::Sha256, b"context: receipts");
+    assert!(matches!(wrong, Err(RsaError::OaepLabelMismatch)));
+
+    let decrypt = ["pkeyutl", "-decrypt", "-inkey", path(&private)];
+    let ciphertext = rsa
+        .encrypt_oaep(message, HashAlgorithm::Sha256, label)
+        .unwrap();
+    assert_eq!(
+        openssl(&[&decrypt[..], &OAEP_SHA256, &label_args].concat(), &ciphertext),
+        message
+    );
+}
+
+// RSA-KEM is raw RSA on a random z followed by KDF2, which is OpenSSL's X963KDF
+// without shared info, so both halves can be reproduced with the command line
+#[test]
+fn kem_matches_openssl() {
+    let dir = TempDir::new("kem");
+    let rsa = RSA::new(2048).unwrap();
+    let private = dir.write("key.pem", rsa.export_private_key_pem().unwrap());
+    let public = dir.write("pub.pem", rsa.export_public_key_spki_pem());
+    let raw = ["-pkeyopt", "rsa_padding_mode:none"];
+    let kdf2 = |z: &[u8]| {
+        let hex: String = z.iter().map(|b| format!("{:02x}", b)).collect();
+        let secret = format!("hexsecret:{}", hex);
+        let args = ["kdf", "-keylen", "48", "-kdfopt", "digest:SHA256", "-kdfopt"];
+        openssl(&[&args[..], &[secret.as_str(), "-binary", "X963KDF"]].concat(), b"")
+    };
+
+    // This crate encapsulates, OpenSSL recovers z and derives the same key
+    let (ciphertext, key) = rsa.encapsulate(Kdf::Kdf2(HashAlgorithm::Sha256), 48);
+    let decrypt = ["pkeyutl", "-decrypt", "-inkey", path(&private)];
+    let z = openssl(&[&decrypt[..], &raw].concat(), &ciphertext);
+    assert_eq!(kdf2(&z), key);
+
+    // OpenSSL encrypts a chosen z, this crate decapsulates it
+    let mut z = vec![0x5A; rsa.ciphertext_len()];
+    z[0] = 0x01;
+    let encrypt = ["pkeyutl", "-encrypt", "-pubin", "-inkey", path(&public)];
+    let ciphertext = openssl(&[&encrypt[..], &raw].concat(), &z);
+    let key = rsa.decapsulate(&ciphertext, Kdf::Kdf2(HashAlgorithm::Sha256), 48);
+    assert_eq!(key.unwrap(), kdf2(&z));
+}
+
+#[test]
+fn signatures_match_openssl() {
+    let dir = TempDir::new("sign");
+    let rsa = RSA::new(2048).unwrap();
+    let private = dir.write("key.pem", rsa.export_private_key_pem().unwrap());
+    let public = dir.write("pub.pem", rsa.export_public_key_spki_pem());
+    let message = b"differential test message";
+
+    for (hash, name) in [
+        (HashAlgorithm::Sha256, "-sha256"),
+        (HashAlgorithm::Sha384, "-sha384"),
+    ] {
+        // PKCS#1 v1.5 is deterministic, so both sides must produce the same bytes
+        let theirs = openssl(&["dgst", name, "-sign", path(&private)], message);
+        let ours = rsa.sign(message, hash).unwrap();
+        assert_eq!(ours, theirs);
+        assert!(rsa.verify(message, &theirs, hash));
+
+        // PSS is randomized: each side verifies the other's signature
+        let pss = [
+            "-sigopt",
+            "rsa_padding_mode:pss",
+            "-sigopt",
+            "rsa_pss_saltlen:digest",
+        ];
+        let theirs = openssl(
+            &[&["dgst", name, "-sign", path(&private)][..], &pss].concat(),
+            message,
+        );
+        assert!(rsa.verify_pss(message, &theirs, hash, hash.output_len()));
+
+        let ours = rsa.sign_pss(message, hash, hash.output_len()).unwrap();
+        let signature = dir.write("sig.bin", &ours);
+        let verify = [
+            "dgst",
+            name,
+            "-verify",
+            path(&public),
+            "-signature",
+            path(&signature),
+        ];
+        openssl(&[&verify[..], &pss].concat(), message);
+    }
+}
+
+#[test]
+fn openssl_keys_import() {
+    let dir = TempDir::new("import");
+    let pem = openssl(
+        &[
+            "genpkey",
+            "-algorithm",
+            "RSA",
+            "-pkeyopt",
+            "rsa_keygen_bits:2048",
+        ],
+        b"",
+    );
+    let rsa = RSA::import_private_key_pkcs8_pem(std::str::from_utf8(&pem).unwrap()).unwrap();
+    let private = dir.write("key.pem", &pem);
+
+    // Re-exporting in PKCS#1 must match OpenSSL's own conversion byte for byte
+    let theirs = openssl(&["rsa", "-in", path(&private), "-traditional"], b"");
+    assert_eq!(
+        rsa.export_private_key_pem().unwrap().trim(),
+        String::from_utf8(theirs).unwrap().trim()
+    );
+    let theirs = openssl(&["rsa", "-in", path(&private), "-pubout"], b"");
+    assert_eq!(
+        rsa.export_public_key_spki_pem().trim(),
+        String::from_utf8(theirs).unwrap().trim()
+    );
+
+    // Deriving d from OpenSSL's primes gives OpenSSL's d, which is reduced mod lambda(n)
+    let rederived = rsa.with_new_exponent(rsa.public_key.0.clone()).unwrap();
+    assert_eq!(rederived.private_key.0, rsa.private_key.0);
+    assert_eq!(
+        rederived.export_private_key_pem().unwrap(),
+        rsa.export_private_key_pem().unwrap()
+    );
+    let mut crt_only = rsa.clone();
+    crt_only.discard_private_exponent().unwrap();
+    assert_eq!(*crt_only.private_exponent(), rsa.private_key.0);
+}
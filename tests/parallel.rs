@@ -0,0 +1,45 @@
+// The `parallel` feature: multi-threaded key generation and batch decryption
+// behave like their single-threaded counterparts (`cargo test --features parallel`).
+
+use rsa_rust::{RSA, RsaError};
+
+#[test]
+fn parallel_keys_are_valid() {
+    for bits in [512, 1024, 1025] {
+        let rsa = RSA::new_parallel(bits).unwrap();
+        assert_eq!(rsa.modulus_bits(), bits as u64);
+        rsa.validate().unwrap();
+        let ciphertext = rsa.encrypt(b"threads").unwrap();
+        assert_eq!(rsa.decrypt(&ciphertext).unwrap(), b"threads");
+    }
+    assert!(matches!(
+        RSA::new_parallel(256),
+        Err(RsaError::KeyTooSmall { bits: 256, .. })
+    ));
+}
+
+#[test]
+fn batch_decryption_keeps_order_and_isolates_errors() {
+    let rsa = RSA::new(1024).unwrap();
+    let messages: Vec<Vec<u8>> = (0..37u8).map(|i| vec![i; i as usize]).collect();
+    let mut ciphertexts: Vec<Vec<u8>> = messages.iter().map(|m| rsa.encrypt(m).unwrap()).collect();
+    // A ciphertext that is too long and one that does not unpad
+    ciphertexts[5] = vec![0; 129];
+    ciphertexts[20] = vec![1; 128];
+
+    let refs: Vec<&[u8]> = ciphertexts.iter().map(Vec::as_slice).collect();
+    let results = rsa.decrypt_batch_parallel(&refs);
+    assert_eq!(results.len(), messages.len());
+    for (i, (result, message)) in results.iter().zip(&messages).enumerate() {
+        match i {
+            5 => assert!(matches!(result, Err(RsaError::InvalidCiphertextLength))),
+            20 => assert!(result.is_err()),
+            _ => assert_eq!(result.as_ref().unwrap(), message, "ciphertext {}", i),
+        }
+    }
+
+    assert!(rsa.decrypt_batch_parallel(&[]).is_empty());
+    let one = rsa.decrypt_batch_parallel(&refs[..1]);
+    assert_eq!(one.len(), 1);
+    assert_eq!(one[0].as_ref().unwrap(), &messages[0]);
+}
@@ -0,0 +1,265 @@
+// PKCS#1 v1.5 and OAEP at the edges of the message length (empty messages, the
+// largest message that fits, and one byte more) and PSS at the edge of the salt length.
+// Decrypting a block with any kind of bad PKCS#1 padding fails with the same error.
+// Ciphertexts with their leading zeros stripped still decrypt; longer ones, or ones
+// at or above the modulus, are rejected.
+
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rsa_rust::rsa::{pkcs1_pad, pkcs1_unpad};
+use rsa_rust::{HashAlgorithm, RSA, RsaError};
+
+// One modulus whose size is a whole number of bytes and one that is not
+fn keys() -> [RSA; 2] {
+    [RSA::new(1024).unwrap(), RSA::new(1031).unwrap()]
+}
+
+#[test]
+fn empty_message_round_trips() {
+    for key in keys() {
+        let ciphertext = key.encrypt(b"").unwrap();
+        assert_eq!(ciphertext.len(), key.modulus_bits().div_ceil(8) as usize);
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), b"");
+
+        let mut out = vec![0xAA; 4];
+        key.decrypt_into(&ciphertext, &mut out).unwrap();
+        assert!(out.is_empty());
+
+        assert!(key.encrypt_chunked(b"").unwrap().is_empty());
+        assert_eq!(key.decrypt_chunked(&[]).unwrap(), b"");
+
+        let ciphertext = key.encrypt_oaep(b"", HashAlgorithm::Sha256, b"").unwrap();
+        let decrypted = key.decrypt_oaep(&ciphertext, HashAlgorithm::Sha256, b"");
+        assert_eq!(decrypted.unwrap(), b"");
+    }
+}
+
+#[test]
+fn longest_message_round_trips() {
+    for key in keys() {
+        let max = key.max_message_len();
+        assert_eq!(max, key.modulus_bits().div_ceil(8) as usize - 11);
+
+        let message = vec![0x5A; max];
+        let ciphertext = key.encrypt(&message).unwrap();
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), message);
+
+        let too_long = vec![0x5A; max + 1];
+        match key.encrypt(&too_long) {
+            Err(RsaError::MessageTooLong { got, max: limit }) => {
+                assert_eq!((got, limit), (max + 1, max));
+            }
+            other => panic!("expected MessageTooLong, got {:?}", other),
+        }
+
+        // OAEP leaves room for two hashes and two more bytes
+        let oaep_max = key.modulus_bits().div_ceil(8) as usize - 2 * 32 - 2;
+        let message = vec![0xA5; oaep_max];
+        let ciphertext = key
+            .encrypt_oaep(&message, HashAlgorithm::Sha256, b"")
+            .unwrap();
+        let decrypted = key.decrypt_oaep(&ciphertext, HashAlgorithm::Sha256, b"");
+        assert_eq!(decrypted.unwrap(), message);
+        let too_long = vec![0xA5; oaep_max + 1];
+        assert!(
+            key.encrypt_oaep(&too_long, HashAlgorithm::Sha256, b"")
+                .is_err()
+        );
+    }
+}
+
+#[test]
+fn padding_boundaries() {
+    // An empty message puts the separator in the last byte
+    let padded = pkcs1_pad(b"", 64).unwrap();
+    assert_eq!(padded[63], 0x00);
+    assert_eq!(pkcs1_unpad(&padded).unwrap(), b"");
+
+    // The longest message leaves exactly the minimum of 8 padding bytes
+    let padded = pkcs1_pad(&[0x01; 53], 64).unwrap();
+    assert!(padded[2..10].iter().all(|&byte| byte != 0));
+    assert_eq!(padded[10], 0x00);
+    assert_eq!(pkcs1_unpad(&padded).unwrap(), [0x01; 53]);
+
+    // Seven padding bytes are one too few
+    let mut short = vec![0x00, 0x02];
+    short.extend_from_slice(&[0xFF; 7]);
+    short.push(0x00);
+    short.extend_from_slice(&[0x01; 54]);
+    assert_eq!(pkcs1_unpad(&short), None);
+
+    // No separator at all
+    let mut unterminated = vec![0x00, 0x02];
+    unterminated.extend_from_slice(&[0xFF; 62]);
+    assert_eq!(pkcs1_unpad(&unterminated), None);
+
+    // The smallest block that can carry a message at all
+    assert_eq!(pkcs1_unpad(&pkcs1_pad(b"", 11).unwrap()).unwrap(), b"");
+    assert!(pkcs1_pad(b"", 10).is_err());
+}
+
+// Encrypts a hand-made block with textbook RSA, padded to the modulus size
+fn encrypt_block(key: &RSA, block: &[u8]) -> Vec<u8> {
+    let c = key
+        .encrypt_raw(&BigUint::from_bytes_be(block))
+        .to_bytes_be();
+    let mut ciphertext = vec![0u8; block.len() - c.len()];
+    ciphertext.extend(c);
+    ciphertext
+}
+
+#[test]
+fn bad_padding_fails_the_same_way() {
+    let key = RSA::new(1024).unwrap();
+    // 00 02 || 100 bytes of padding || 00 || 25-byte message
+    let mut good = vec![0x00, 0x02];
+    good.extend_from_slice(&[0x5A; 100]);
+    good.push(0x00);
+    good.extend_from_slice(&[0x01; 25]);
+    assert_eq!(
+        key.decrypt(&encrypt_block(&key, &good)).unwrap(),
+        [0x01; 25]
+    );
+
+    let mut first_byte = good.clone();
+    first_byte[0] = 0x01;
+    // Block type 1 is the signature padding, not encryption
+    let mut block_type_1 = good.clone();
+    block_type_1[1] = 0x01;
+    let mut block_type_0 = good.clone();
+    block_type_0[1] = 0x00;
+    let mut no_separator = good.clone();
+    no_separator[102] = 0x5A;
+    // A zero right after the header, and one leaving only 7 padding bytes
+    let mut separator_at_start = good.clone();
+    separator_at_start[2] = 0x00;
+    let mut separator_after_7 = good.clone();
+    separator_after_7[9] = 0x00;
+
+    let cases = [
+        ("wrong first byte", first_byte),
+        ("block type 1", block_type_1),
+        ("block type 0", block_type_0),
+        ("no separator", no_separator),
+        ("separator at the start", separator_at_start),
+        ("separator after 7 bytes", separator_after_7),
+    ];
+    for (name, block) in cases {
+        let ciphertext = encrypt_block(&key, &block);
+        let errors = [
+            key.decrypt(&ciphertext).unwrap_err(),
+            key.decrypt_unblinded(&ciphertext).unwrap_err(),
+            key.decrypt_into(&ciphertext, &mut Vec::new()).unwrap_err(),
+        ];
+        for err in errors {
+            assert!(matches!(err, RsaError::InvalidPadding), "{name}: {err:?}");
+        }
+    }
+}
+
+#[test]
+fn stripped_leading_zeros_decrypt() {
+    for key in keys() {
+        let modulus_bytes = key.modulus_bits().div_ceil(8) as usize;
+        // About one ciphertext in 256 (fewer for a small top byte) starts with zero
+        let find = |encrypt: &dyn Fn() -> Vec<u8>| loop {
+            let ciphertext = encrypt();
+            if ciphertext[0] == 0 {
+                return ciphertext;
+            }
+        };
+
+        let ciphertext = find(&|| key.encrypt(b"stripped").unwrap());
+        assert_eq!(ciphertext.len(), modulus_bytes);
+        assert_eq!(key.decrypt(&ciphertext[1..]).unwrap(), b"stripped");
+        assert_eq!(
+            key.decrypt_unblinded(&ciphertext[1..]).unwrap(),
+            b"stripped"
+        );
+
+        let ciphertext = find(&|| {
+            key.encrypt_oaep(b"stripped", HashAlgorithm::Sha256, b"")
+                .unwrap()
+        });
+        let decrypted = key.decrypt_oaep(&ciphertext[1..], HashAlgorithm::Sha256, b"");
+        assert_eq!(decrypted.unwrap(), b"stripped");
+    }
+}
+
+#[test]
+fn oversized_ciphertexts_are_rejected() {
+    for key in keys() {
+        let modulus_bytes = key.modulus_bits().div_ceil(8) as usize;
+        let ciphertext = key.encrypt(b"oversized").unwrap();
+
+        // One extra leading zero keeps the value below n, but not the length
+        let mut padded = vec![0x00];
+        padded.extend_from_slice(&ciphertext);
+        let far_too_long = vec![0x01; 1 << 20];
+        for long in [&padded, &far_too_long] {
+            assert!(matches!(
+                key.decrypt(long),
+                Err(RsaError::InvalidCiphertextLength)
+            ));
+            assert!(matches!(
+                key.decrypt_oaep(long, HashAlgorithm::Sha256, b""),
+                Err(RsaError::InvalidCiphertextLength)
+            ));
+        }
+
+        // Right length, but c >= n would be reduced mod n and decrypt to garbage
+        let n = key.public().n.to_bytes_be();
+        let n_plus_one = (&key.public().n + 1u32).to_bytes_be();
+        let all_ones = vec![0xFF; modulus_bytes];
+        for big in [&n, &n_plus_one, &all_ones] {
+            assert_eq!(big.len(), modulus_bytes);
+            assert!(matches!(
+                key.decrypt(big),
+                Err(RsaError::CiphertextOutOfRange)
+            ));
+            assert!(matches!(
+                key.decrypt_oaep(big, HashAlgorithm::Sha256, b""),
+                Err(RsaError::CiphertextOutOfRange)
+            ));
+        }
+    }
+}
+
+#[test]
+fn oaep_with_a_seeded_rng_is_reproducible() {
+    let key = RSA::new(1024).unwrap();
+    let encrypt = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        key.encrypt_oaep_with_rng(b"seeded", HashAlgorithm::Sha256, b"", &mut rng)
+            .unwrap()
+    };
+    let ciphertext = encrypt(7);
+    assert_eq!(ciphertext, encrypt(7));
+    assert_ne!(ciphertext, encrypt(8));
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let decrypted = key.decrypt_oaep_with_rng(&ciphertext, HashAlgorithm::Sha256, b"", &mut rng);
+    assert_eq!(decrypted.unwrap(), b"seeded");
+}
+
+// Salt lengths are checked before the salt is allocated, so an absurd one is just
+// an error
+#[test]
+fn pss_salt_length_limits() {
+    let key = RSA::new(1024).unwrap();
+    // emLen = 128 bytes leaves 128 - 32 - 2 for the salt
+    let max = 94;
+    let signature = key.sign_pss(b"salted", HashAlgorithm::Sha256, max).unwrap();
+    assert!(key.verify_pss(b"salted", &signature, HashAlgorithm::Sha256, max));
+
+    for salt_len in [max + 1, usize::MAX] {
+        match key.sign_pss(b"salted", HashAlgorithm::Sha256, salt_len) {
+            Err(RsaError::InvalidSaltLength { got, max: limit }) => {
+                assert_eq!((got, limit), (salt_len, max));
+            }
+            other => panic!("expected InvalidSaltLength, got {:?}", other),
+        }
+        assert!(!key.verify_pss(b"salted", &signature, HashAlgorithm::Sha256, salt_len));
+    }
+}
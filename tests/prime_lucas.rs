@@ -0,0 +1,59 @@
+// The strong Lucas test against Miller-Rabin: its pseudoprimes below 100,000
+// (OEIS A217255) all fail Miller-Rabin, and on random odd numbers both tests
+// accept exactly the same candidates.
+
+use num_bigint::{BigUint, RandBigInt};
+use rsa_rust::prime::{generate_prime_with_lucas, is_lucas_prime, is_prime, is_prime_with_lucas};
+
+const LUCAS_PSEUDOPRIMES: [u32; 12] = [
+    5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519, 75077, 97439,
+];
+
+fn is_prime_trial(n: u32) -> bool {
+    n >= 2
+        && (2..n)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+#[test]
+fn lucas_errors_below_100000_are_its_known_pseudoprimes() {
+    let mut rng = rand::thread_rng();
+    for n in 0u32..100_000 {
+        let n_big = BigUint::from(n);
+        let expected = is_prime_trial(n);
+        let lucas_wrong = is_lucas_prime(&n_big) != expected;
+        assert_eq!(lucas_wrong, LUCAS_PSEUDOPRIMES.contains(&n), "{}", n);
+        assert_eq!(is_prime(&n_big, 1, &mut rng), expected, "{}", n);
+        assert_eq!(is_prime_with_lucas(&n_big, 1, &mut rng), expected, "{}", n);
+    }
+}
+
+#[test]
+fn accept_rates_match_miller_rabin() {
+    let mut rng = rand::thread_rng();
+    let (mut mr, mut lucas, mut both) = (0, 0, 0);
+    for _ in 0..2000 {
+        let n = rng.gen_biguint(256) | BigUint::from(1u32);
+        let mr_accepts = is_prime(&n, 20, &mut rng);
+        let lucas_accepts = is_lucas_prime(&n);
+        assert_eq!(mr_accepts, lucas_accepts, "{}", n);
+        mr += usize::from(mr_accepts);
+        lucas += usize::from(lucas_accepts);
+        both += usize::from(is_prime_with_lucas(&n, 20, &mut rng));
+    }
+    assert_eq!(mr, lucas);
+    assert_eq!(mr, both);
+    // About 2 / ln(2^256), i.e. one odd number in 89, is prime
+    assert!((8..60).contains(&mr), "{} primes", mr);
+}
+
+#[test]
+fn generated_primes_pass_both_tests() {
+    let mut rng = rand::thread_rng();
+    for bits in [64, 256, 512] {
+        let p = generate_prime_with_lucas(bits, 20, &mut rng);
+        assert_eq!(p.bits(), bits as u64);
+        assert!(is_prime(&p, 20, &mut rng) && is_lucas_prime(&p));
+    }
+}
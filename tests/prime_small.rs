@@ -0,0 +1,84 @@
+// Primality tests on the smallest inputs, where `n - 1` and `n - 2` would
+// underflow if the early returns were ever reordered, and prime generation at
+// the smallest bit lengths.
+
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rsa_rust::prime::{
+    generate_prime, generate_prime_with_lucas, generate_prime_with_rounds, is_prime, is_prime_bpsw,
+    prime_iter, prime_iter_with_rng,
+};
+
+#[test]
+fn zero_to_four() {
+    let mut rng = rand::thread_rng();
+    for (n, expected) in [(0u32, false), (1, false), (2, true), (3, true), (4, false)] {
+        let n_big = BigUint::from(n);
+        assert_eq!(is_prime(&n_big, 1, &mut rng), expected, "is_prime({})", n);
+        assert_eq!(is_prime_bpsw(&n_big), expected, "is_prime_bpsw({})", n);
+    }
+}
+
+#[test]
+fn agrees_with_trial_division_below_5000() {
+    let mut rng = rand::thread_rng();
+    for n in 0u32..5000 {
+        let expected = n >= 2
+            && (2..n)
+                .take_while(|d| d * d <= n)
+                .all(|d| !n.is_multiple_of(d));
+        let n_big = BigUint::from(n);
+        assert_eq!(is_prime(&n_big, 1, &mut rng), expected, "is_prime({})", n);
+        assert_eq!(is_prime_bpsw(&n_big), expected, "is_prime_bpsw({})", n);
+    }
+}
+
+#[test]
+fn prime_iter_at_the_smallest_sizes() {
+    assert!(prime_iter(2).take(20).all(|p| p == BigUint::from(3u8)));
+    let mut rng = rand::thread_rng();
+    for p in prime_iter_with_rng(3, &mut rng).take(20) {
+        assert!(p == BigUint::from(5u8) || p == BigUint::from(7u8), "{}", p);
+    }
+}
+
+#[test]
+#[should_panic(expected = "no prime has fewer than 2 bits")]
+fn prime_iter_rejects_one_bit() {
+    let _ = prime_iter(1);
+}
+
+#[test]
+#[should_panic(expected = "no prime has fewer than 2 bits")]
+fn prime_iter_rejects_zero_bits() {
+    let _ = prime_iter_with_rng(0, rand::thread_rng());
+}
+
+#[test]
+#[should_panic(expected = "no prime has fewer than 2 bits")]
+fn generate_prime_rejects_one_bit() {
+    generate_prime(1, &mut StdRng::seed_from_u64(1));
+}
+
+#[test]
+#[should_panic(expected = "no prime has fewer than 2 bits")]
+fn generate_prime_with_rounds_rejects_zero_bits() {
+    generate_prime_with_rounds(0, 10, &mut StdRng::seed_from_u64(0));
+}
+
+#[test]
+#[should_panic(expected = "no prime has fewer than 2 bits")]
+fn generate_prime_with_lucas_rejects_one_bit() {
+    generate_prime_with_lucas(1, 10, &mut StdRng::seed_from_u64(1));
+}
+
+#[test]
+fn two_bit_prime_is_three() {
+    let mut rng = StdRng::seed_from_u64(2);
+    assert_eq!(generate_prime(2, &mut rng), BigUint::from(3u8));
+    assert_eq!(
+        generate_prime_with_lucas(2, 10, &mut rng),
+        BigUint::from(3u8)
+    );
+}
@@ -0,0 +1,63 @@
+// Safe primes p = 2q + 1: with a seeded RNG at small sizes, both p and (p - 1) / 2
+// have to be prime, and so do the halves of a safe-prime key's p and q. The sizes
+// with no safe prime at all are rejected instead of searched forever.
+
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rsa_rust::RsaKeyGenBuilder;
+use rsa_rust::prime::{generate_safe_prime_with_rounds, is_prime};
+
+fn assert_safe(p: &BigUint, rng: &mut StdRng) {
+    assert!(is_prime(p, 40, rng), "{p} is not prime");
+    let q: BigUint = (p - 1u32) >> 1;
+    assert!(is_prime(&q, 40, rng), "({p} - 1) / 2 = {q} is not prime");
+}
+
+#[test]
+fn generated_safe_primes_are_safe() {
+    let mut rng = StdRng::seed_from_u64(50);
+    for bits in (3..=64).filter(|bits| !(4..=5).contains(bits)) {
+        for _ in 0..5 {
+            let p = generate_safe_prime_with_rounds(bits, 40, &mut rng);
+            assert_eq!(p.bits(), bits as u64);
+            assert_safe(&p, &mut rng);
+        }
+    }
+    // The only safe primes at 3 and 6 bits
+    assert_eq!(
+        generate_safe_prime_with_rounds(3, 40, &mut rng),
+        BigUint::from(7u8)
+    );
+    assert_eq!(
+        generate_safe_prime_with_rounds(6, 40, &mut rng),
+        BigUint::from(59u8)
+    );
+}
+
+#[test]
+#[should_panic(expected = "no 4-bit safe prime")]
+fn no_four_bit_safe_prime() {
+    generate_safe_prime_with_rounds(4, 40, &mut StdRng::seed_from_u64(4));
+}
+
+#[test]
+#[should_panic(expected = "no 5-bit safe prime")]
+fn no_five_bit_safe_prime() {
+    generate_safe_prime_with_rounds(5, 40, &mut StdRng::seed_from_u64(5));
+}
+
+#[test]
+fn safe_prime_key_has_safe_primes() {
+    let rsa = RsaKeyGenBuilder::new()
+        .bits(512)
+        .safe_primes(true)
+        .rng(StdRng::seed_from_u64(5050))
+        .generate()
+        .unwrap();
+    let (p, q) = rsa.primes().unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+    assert_safe(p, &mut rng);
+    assert_safe(q, &mut rng);
+    assert_eq!(rsa.modulus_bits(), 512);
+}
@@ -0,0 +1,73 @@
+// Incremental signing and verification: signatures match one-shot `sign` whatever
+// the split of the input, and a `Verifier` rejects other keys, hashes and data.
+
+use std::io::{self, Write};
+
+use rsa_rust::{HashAlgorithm, RSA};
+
+const MESSAGE: &[u8] = b"the quick brown fox jumps over the lazy dog, several times over";
+
+#[test]
+fn signer_matches_one_shot_sign() {
+    let rsa = RSA::new(1024).unwrap();
+    for hash in [HashAlgorithm::Sha256, HashAlgorithm::Sha384] {
+        let expected = rsa.sign(MESSAGE, hash).unwrap();
+        for split in [0, 1, 17, MESSAGE.len()] {
+            let mut signer = rsa.signer(hash);
+            signer.update(&MESSAGE[..split]);
+            signer.update(&MESSAGE[split..]);
+            assert_eq!(signer.finalize().unwrap(), expected);
+        }
+
+        let mut signer = rsa.signer(hash);
+        io::copy(&mut &MESSAGE[..], &mut signer).unwrap();
+        assert_eq!(signer.finalize().unwrap(), expected);
+    }
+}
+
+#[test]
+fn verifier_accepts_matching_signatures() {
+    let rsa = RSA::new(1024).unwrap();
+    let signature = rsa.sign(MESSAGE, HashAlgorithm::Sha256).unwrap();
+
+    let mut verifier = rsa.verifier(HashAlgorithm::Sha256, &signature);
+    for chunk in MESSAGE.chunks(10) {
+        verifier.update(chunk);
+    }
+    assert!(verifier.finalize());
+
+    let mut verifier = rsa.public().verifier(HashAlgorithm::Sha256, &signature);
+    verifier.write_all(MESSAGE).unwrap();
+    assert!(verifier.finalize());
+}
+
+#[test]
+fn verifier_rejects_mismatches() {
+    let rsa = RSA::new(1024).unwrap();
+    let other = RSA::new(1024).unwrap();
+    let signature = rsa.sign(MESSAGE, HashAlgorithm::Sha256).unwrap();
+    let verify = |key: &RSA, hash, signature: &[u8], data: &[u8]| {
+        let mut verifier = key.verifier(hash, signature);
+        verifier.update(data);
+        verifier.finalize()
+    };
+
+    assert!(!verify(&other, HashAlgorithm::Sha256, &signature, MESSAGE));
+    assert!(!verify(&rsa, HashAlgorithm::Sha384, &signature, MESSAGE));
+    assert!(!verify(
+        &rsa,
+        HashAlgorithm::Sha256,
+        &signature,
+        &MESSAGE[1..]
+    ));
+    assert!(!verify(
+        &rsa,
+        HashAlgorithm::Sha256,
+        &signature[1..],
+        MESSAGE
+    ));
+    let mut flipped = signature.clone();
+    flipped[0] ^= 1;
+    assert!(!verify(&rsa, HashAlgorithm::Sha256, &flipped, MESSAGE));
+    assert!(verify(&rsa, HashAlgorithm::Sha256, &signature, MESSAGE));
+}
@@ -0,0 +1,152 @@
+// Streaming encryption: round trips around the 64 KiB chunk boundary, and
+// truncated, reordered, modified or extended streams rejected as invalid data.
+
+use std::io::ErrorKind;
+
+use rsa_rust::RSA;
+
+const CHUNK_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+
+fn key() -> RSA {
+    RSA::new(1024).unwrap()
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 + 7) as u8).collect()
+}
+
+fn encrypt(rsa: &RSA, plaintext: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    rsa.public().encrypt_stream(plaintext, &mut out).unwrap();
+    out
+}
+
+fn decrypt(rsa: &RSA, stream: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    rsa.decrypt_stream(stream, &mut out)?;
+    Ok(out)
+}
+
+// Splits a stream into its header and its length-prefixed chunk frames
+fn frames(stream: &[u8]) -> (&[u8], Vec<&[u8]>) {
+    let key_len = u16::from_be_bytes([stream[0], stream[1]]) as usize;
+    let (header, mut rest) = stream.split_at(2 + key_len + 8);
+    let mut frames = Vec::new();
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let (frame, tail) = rest.split_at(4 + len);
+        frames.push(frame);
+        rest = tail;
+    }
+    (header, frames)
+}
+
+fn join(header: &[u8], frames: &[&[u8]]) -> Vec<u8> {
+    let mut stream = header.to_vec();
+    for frame in frames {
+        stream.extend_from_slice(frame);
+    }
+    stream
+}
+
+fn assert_invalid(rsa: &RSA, stream: &[u8]) {
+    let err = decrypt(rsa, stream).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData, "{err}");
+}
+
+#[test]
+fn round_trips_at_chunk_boundaries() {
+    let rsa = key();
+    // plaintext length, number of chunks (a full last chunk is followed by an empty one)
+    let cases = [
+        (0, 1),
+        (1, 1),
+        (CHUNK_LEN - 1, 1),
+        (CHUNK_LEN, 2),
+        (CHUNK_LEN + 1, 2),
+        (3 * CHUNK_LEN, 4),
+    ];
+    for (len, chunks) in cases {
+        let plaintext = plaintext(len);
+        let stream = encrypt(&rsa, &plaintext);
+        let (header, frames) = frames(&stream);
+        assert_eq!(header.len(), 2 + 128 + 8);
+        assert_eq!(frames.len(), chunks, "{len} bytes");
+        assert_eq!(stream.len(), header.len() + len + chunks * (4 + TAG_LEN));
+        assert_eq!(decrypt(&rsa, &stream).unwrap(), plaintext, "{len} bytes");
+    }
+}
+
+#[test]
+fn truncated_streams_are_rejected() {
+    let rsa = key();
+    let stream = encrypt(&rsa, &plaintext(2 * CHUNK_LEN + 10));
+    let (header, frames) = frames(&stream);
+    assert_eq!(frames.len(), 3);
+
+    // Dropping whole chunks leaves a stream without its last-chunk marker
+    for keep in 0..frames.len() {
+        let err = decrypt(&rsa, &join(header, &frames[..keep])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{keep} chunks");
+    }
+    // Cutting into a frame or the header
+    for cut in [stream.len() - 1, stream.len() - 20, header.len() + 2, 1] {
+        assert!(decrypt(&rsa, &stream[..cut]).is_err(), "cut at {cut}");
+    }
+}
+
+#[test]
+fn reordered_chunks_are_rejected() {
+    let rsa = key();
+    let stream = encrypt(&rsa, &plaintext(3 * CHUNK_LEN + 10));
+    let (header, frames) = frames(&stream);
+
+    let mut swapped = frames.clone();
+    swapped.swap(0, 1);
+    assert_invalid(&rsa, &join(header, &swapped));
+
+    // So does replaying a chunk in a later position
+    let mut replayed = frames.clone();
+    replayed[1] = frames[0];
+    assert_invalid(&rsa, &join(header, &replayed));
+}
+
+#[test]
+fn modified_chunks_are_rejected() {
+    let rsa = key();
+    let stream = encrypt(&rsa, &plaintext(CHUNK_LEN + 100));
+    let (header, frames) = frames(&stream);
+
+    let mut offset = header.len();
+    for frame in &frames {
+        // First ciphertext byte and last tag byte of every chunk
+        for pos in [offset + 4, offset + frame.len() - 1] {
+            let mut tampered = stream.clone();
+            tampered[pos] ^= 0x01;
+            assert_invalid(&rsa, &tampered);
+        }
+        offset += frame.len();
+    }
+
+    // A frame length beyond a full chunk is rejected before reading the body
+    let mut oversized = stream.clone();
+    oversized[header.len()..header.len() + 4]
+        .copy_from_slice(&((CHUNK_LEN + TAG_LEN + 1) as u32).to_be_bytes());
+    assert_invalid(&rsa, &oversized);
+}
+
+#[test]
+fn extended_streams_are_rejected() {
+    let rsa = key();
+    let stream = encrypt(&rsa, &plaintext(100));
+
+    let mut trailing = stream.clone();
+    trailing.push(0);
+    assert_invalid(&rsa, &trailing);
+
+    // A second complete stream appended to the first
+    let mut doubled = stream.clone();
+    doubled.extend_from_slice(&stream);
+    assert_invalid(&rsa, &doubled);
+}
@@ -0,0 +1,203 @@
+// Known-answer tests from every `.rsp` file in tests/data, so a new vector file
+// needs no code changes.
+//
+// The files use the NIST CAVP layout, which SigGen15 / SigVer15 / SigGenPSS
+// downloads can be dropped in as-is:
+//
+//     # comment
+//     [mod = 2048]        section headers are ignored
+//
+//     n = c47a...         key = hex value, one per line
+//     e = 010001
+//     d = 0997...
+//
+//     SHAAlg = SHA256     a blank line ends a record
+//     Msg = d73829...
+//     S = 11a92f...
+//
+// n, e, d, SHAAlg and Padding carry over to later records until they are
+// restated; every record with a `Msg` is one test case:
+//
+// * `S` with no `SaltVal`: PKCS#1 v1.5 signature. It must verify, and signing
+//   `Msg` must reproduce it; with `Result = F...` it must fail to verify.
+// * `S` with `SaltVal`: PSS signature, which must verify with the salt's length.
+// * `CT`: ciphertext under `Padding` (none, pkcs1 or oaep with SHAAlg and an
+//   empty label), which must decrypt to `Msg`. With no padding, encrypting `Msg`
+//   must also reproduce `CT`.
+//
+// Cases with a hash the crate does not implement (e.g. SHA-1) are skipped.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use num_bigint::BigUint;
+use rsa_rust::{HashAlgorithm, RSA};
+
+const CARRIED: [&str; 5] = ["n", "e", "d", "SHAAlg", "Padding"];
+
+struct Case {
+    line: usize,
+    fields: HashMap<String, String>,
+}
+
+enum Outcome {
+    Pass,
+    Skip,
+    Fail(String),
+}
+
+#[test]
+fn known_answer_vectors() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .expect("tests/data is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rsp"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no .rsp files in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for path in &files {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let text = fs::read_to_string(path).unwrap();
+        let (mut passed, mut skipped) = (0, 0);
+        for case in parse(&text) {
+            match run(&case) {
+                Outcome::Pass => passed += 1,
+                Outcome::Skip => skipped += 1,
+                Outcome::Fail(why) => failures.push(format!("{}:{}: {}", name, case.line, why)),
+            }
+        }
+        println!("{}: {} passed, {} skipped", name, passed, skipped);
+        assert!(passed > 0, "{} contains no runnable cases", name);
+    }
+    assert!(
+        failures.is_empty(),
+        "failed vectors:\n{}",
+        failures.join("\n")
+    );
+}
+
+fn parse(text: &str) -> Vec<Case> {
+    let mut cases = Vec::new();
+    let mut carried: HashMap<String, String> = HashMap::new();
+    let mut record: HashMap<String, String> = HashMap::new();
+    let mut start = 0;
+
+    // A trailing empty line flushes the last record
+    for (index, line) in text.lines().chain([""]).enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            if record.contains_key("Msg") {
+                let mut fields = carried.clone();
+                fields.extend(record.drain());
+                cases.push(Case {
+                    line: start,
+                    fields,
+                });
+            }
+            record.clear();
+            continue;
+        }
+        if line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_string(), value.trim().to_string());
+        if record.is_empty() {
+            start = index + 1;
+        }
+        if CARRIED.contains(&key.as_str()) {
+            carried.insert(key.clone(), value.clone());
+        }
+        record.insert(key, value);
+    }
+    cases
+}
+
+fn run(case: &Case) -> Outcome {
+    let field = |key: &str| case.fields.get(key).map(String::as_str);
+    let (Some(n), Some(e)) = (field("n"), field("e")) else {
+        return Outcome::Fail("missing n or e".into());
+    };
+    let n = BigUint::parse_bytes(n.as_bytes(), 16).unwrap();
+    let e = BigUint::parse_bytes(e.as_bytes(), 16).unwrap();
+    let d = field("d").map(|d| BigUint::parse_bytes(d.as_bytes(), 16).unwrap());
+    let key = RSA::from_components(e, d.clone().unwrap_or_default(), n);
+    let msg = hex(field("Msg").unwrap());
+
+    let hash = match field("SHAAlg") {
+        None => None,
+        Some("SHA256") => Some(HashAlgorithm::Sha256),
+        Some("SHA384") => Some(HashAlgorithm::Sha384),
+        Some(_) => return Outcome::Skip,
+    };
+
+    if let Some(sig) = field("S") {
+        let sig = hex(sig);
+        let Some(hash) = hash else {
+            return Outcome::Fail("signature without SHAAlg".into());
+        };
+        let expect_valid = field("Result").is_none_or(|result| result.starts_with('P'));
+
+        if let Some(salt) = field("SaltVal") {
+            let salt_len = hex(salt).len();
+            if key.verify_pss(&msg, &sig, hash, salt_len) != expect_valid {
+                return Outcome::Fail(format!("PSS verify did not return {}", expect_valid));
+            }
+            return Outcome::Pass;
+        }
+        if key.verify(&msg, &sig, hash) != expect_valid {
+            return Outcome::Fail(format!("verify did not return {}", expect_valid));
+        }
+        if expect_valid && d.is_some() && key.sign(&msg, hash).unwrap() != sig {
+            return Outcome::Fail("sign produced a different signature".into());
+        }
+        return Outcome::Pass;
+    }
+
+    let Some(ct) = field("CT") else {
+        return Outcome::Fail("record has neither S nor CT".into());
+    };
+    let ct = hex(ct);
+    if d.is_none() {
+        return Outcome::Fail("ciphertext vector without d".into());
+    }
+    let decrypted = match field("Padding").unwrap_or("none") {
+        "none" => {
+            let m = BigUint::from_bytes_be(&msg);
+            if key.encrypt_raw(&m) != BigUint::from_bytes_be(&ct) {
+                return Outcome::Fail("encrypt_raw produced a different ciphertext".into());
+            }
+            let m = key.decrypt_raw(&BigUint::from_bytes_be(&ct));
+            return if m == BigUint::from_bytes_be(&msg) {
+                Outcome::Pass
+            } else {
+                Outcome::Fail("decrypt_raw produced a different message".into())
+            };
+        }
+        "pkcs1" => key.decrypt(&ct),
+        "oaep" => match hash {
+            Some(hash) => key.decrypt_oaep(&ct, hash, b""),
+            None => return Outcome::Fail("OAEP vector without SHAAlg".into()),
+        },
+        other => return Outcome::Fail(format!("unknown padding {}", other)),
+    };
+    match decrypted {
+        Ok(m) if m == msg => Outcome::Pass,
+        Ok(_) => Outcome::Fail("decrypted to a different message".into()),
+        Err(err) => Outcome::Fail(format!("decryption failed: {}", err)),
+    }
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    assert!(s.len().is_multiple_of(2), "odd-length hex value");
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("invalid hex"))
+        .collect()
+}